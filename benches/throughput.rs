@@ -0,0 +1,49 @@
+//! Byte throughput through a ring, next to a plain `memcpy` for comparison.
+//!
+//! Run with `cargo bench --bench throughput`.
+use std::hint::black_box;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use horrid_ring_buffer::HorridRing;
+
+const TOTAL: usize = 256 * 1024 * 1024;
+const RING: usize = 64 * 1024;
+const CHUNK: usize = 16 * 1024 + 7;
+
+fn report(name: &str, elapsed: Duration) {
+    let gib = TOTAL as f64 / (1024.0 * 1024.0 * 1024.0);
+    println!("{:<12} {:>8.2} GiB/s", name, gib / elapsed.as_secs_f64());
+}
+
+fn main() {
+    let src = vec![0xAB_u8; CHUNK];
+    let mut dst = vec![0_u8; CHUNK];
+
+    // Baseline: the same number of bytes copied between two plain buffers
+    let start = Instant::now();
+    for _ in 0..TOTAL / CHUNK {
+        dst.copy_from_slice(black_box(&src));
+        black_box(&mut dst);
+    }
+    report("memcpy", start.elapsed());
+
+    // The odd chunk size keeps moving the wrap point around the ring
+    let mut ring = HorridRing::with_capacity(RING);
+    let start = Instant::now();
+    for _ in 0..TOTAL / CHUNK {
+        ring.write_all(black_box(&src)).unwrap();
+        ring.read_exact(&mut dst).unwrap();
+        black_box(&mut dst);
+    }
+    report("io", start.elapsed());
+
+    let mut ring = HorridRing::with_capacity(RING);
+    let start = Instant::now();
+    for _ in 0..TOTAL / CHUNK {
+        ring.push_slice(black_box(&src));
+        ring.pop_slice(&mut dst);
+        black_box(&mut dst);
+    }
+    report("slice", start.elapsed());
+}
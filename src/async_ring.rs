@@ -0,0 +1,724 @@
+//! Async producer and consumer halves of a [`HorridRing`].
+//!
+//! Pushing waits while the ring is full and popping waits while it is empty.
+//! The consumer is a [`Stream`] that ends once the producer is dropped or
+//! closed, and the producer is a [`Sink`].
+//!
+//! Both [`Producer::push`] and [`Consumer::pop`] are cancellation safe:
+//! dropping a `push` future before it completes drops the value without
+//! touching the ring, and dropping a `pop` future never loses a value.
+//!
+//! ```
+//! use horrid_ring_buffer::HorridRing;
+//!
+//! futures::executor::block_on(async {
+//!     let (mut producer, mut consumer) = HorridRing::with_capacity(4).split_async();
+//!     producer.push(1).await.unwrap();
+//!     drop(producer);
+//!
+//!     assert_eq!(consumer.pop().await, Some(1));
+//!     assert_eq!(consumer.pop().await, None);
+//! });
+//! ```
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::{spsc, HorridError, HorridRing};
+
+// -----------------------------------------------------------------------------
+//     - Signal -
+// -----------------------------------------------------------------------------
+// A waker slot for one side of the ring.
+//
+// A task registers its waker and then checks the ring again, while the other
+// side changes the ring and then takes the waker. As both go through the lock,
+// either the task sees the change or the other side sees the waker.
+#[derive(Default)]
+struct Signal {
+    waker: Mutex<Option<Waker>>,
+    closed: AtomicBool,
+}
+
+impl Signal {
+    fn register(&self, waker: &Waker) {
+        let mut slot = self.waker.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.as_mut() {
+            Some(old) if old.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        }
+    }
+
+    fn wake(&self) {
+        let waker = self.waker.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Default)]
+struct Signals {
+    // Woken when there is room, closed when the producer is done
+    producer: Signal,
+    // Woken when there are values, closed when the consumer is gone
+    consumer: Signal,
+}
+
+// -----------------------------------------------------------------------------
+//     - Split -
+// -----------------------------------------------------------------------------
+impl<T> HorridRing<T> {
+    /// Split the ring into an async producer and consumer.
+    /// Values already in the ring stay there.
+    ///
+    /// Like [`split`](Self::split) the halves never overwrite:
+    /// pushing to a full ring waits for the consumer to make room.
+    pub fn split_async(self) -> (Producer<T>, Consumer<T>) {
+        let (producer, consumer) = self.split();
+        let signals = Arc::new(Signals::default());
+
+        let producer = Producer {
+            inner: producer,
+            signals: signals.clone(),
+        };
+
+        (producer, Consumer { inner: consumer, signals })
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Producer -
+// -----------------------------------------------------------------------------
+/// The pushing half of an async split [`HorridRing`].
+pub struct Producer<T> {
+    inner: spsc::Producer<T>,
+    signals: Arc<Signals>,
+}
+
+impl<T> Producer<T> {
+    /// Push a value, waiting for room if the ring is full.
+    ///
+    /// Fails with the value if the consumer is gone.
+    pub async fn push(&mut self, val: T) -> Result<(), T> {
+        if poll_fn(|cx| self.poll_ready(cx)).await.is_err() {
+            return Err(val);
+        }
+
+        self.try_push(val)
+    }
+
+    /// Push a value if there is room for it, without waiting.
+    ///
+    /// Fails with the value if either half has been closed, as the
+    /// consumer would never pop it.
+    pub fn try_push(&mut self, val: T) -> Result<(), T> {
+        if self.is_closed() {
+            return Err(val);
+        }
+
+        self.inner.try_push(val)?;
+        self.signals.consumer.wake();
+        Ok(())
+    }
+
+    /// Ready once there is room for a value, or with
+    /// [`HorridError::Disconnected`] if either half has been closed.
+    ///
+    /// Only this producer can fill the ring, so the room is still
+    /// there when it gets around to pushing.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), HorridError>> {
+        if self.is_closed() {
+            return Poll::Ready(Err(HorridError::Disconnected));
+        }
+
+        if self.has_room() {
+            return Poll::Ready(Ok(()));
+        }
+
+        self.signals.producer.register(cx.waker());
+
+        if self.is_closed() {
+            Poll::Ready(Err(HorridError::Disconnected))
+        } else if self.has_room() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Tell the consumer no more values are coming. Once it has
+    /// popped the values already in the ring, its stream ends.
+    pub fn close(&mut self) {
+        self.signals.producer.close();
+        self.signals.consumer.wake();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.signals.producer.is_closed() || self.signals.consumer.is_closed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn has_room(&self) -> bool {
+        self.inner.len() < self.inner.capacity()
+    }
+}
+
+impl<T> Sink<T> for Producer<T> {
+    type Error = HorridError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let producer = self.get_mut();
+        if producer.is_closed() {
+            return Err(HorridError::Disconnected);
+        }
+
+        producer.try_push(item).map_err(|_| HorridError::Full)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Values are in the ring as soon as they are sent
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Consumer -
+// -----------------------------------------------------------------------------
+/// The popping half of an async split [`HorridRing`].
+pub struct Consumer<T> {
+    inner: spsc::Consumer<T>,
+    signals: Arc<Signals>,
+}
+
+impl<T> Consumer<T> {
+    /// Pop the oldest value, waiting for one if the ring is empty.
+    ///
+    /// Returns `None` once the ring is empty and the producer is closed or gone.
+    pub async fn pop(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_pop(cx)).await
+    }
+
+    /// Pop the oldest value if there is one, without waiting.
+    pub fn try_pop(&mut self) -> Option<T> {
+        let val = self.inner.pop()?;
+        self.signals.producer.wake();
+        Some(val)
+    }
+
+    pub fn poll_pop(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(val) = self.try_pop() {
+            return Poll::Ready(Some(val));
+        }
+
+        self.signals.consumer.register(cx.waker());
+
+        // The producer may have pushed before closing, so look once more
+        let closed = self.signals.producer.is_closed();
+        match self.try_pop() {
+            Some(val) => Poll::Ready(Some(val)),
+            None if closed => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+
+    /// Stop taking values. The producer fails on its next push.
+    pub fn close(&mut self) {
+        self.signals.consumer.close();
+        self.signals.producer.wake();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.signals.producer.is_closed() || self.signals.consumer.is_closed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+}
+
+impl<T> Stream for Consumer<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_pop(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), None)
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Bytes -
+// -----------------------------------------------------------------------------
+impl Producer<u8> {
+    /// Write as many bytes as there is room for.
+    ///
+    /// Pending while the ring is full, and fails with
+    /// [`ErrorKind::BrokenPipe`](io::ErrorKind::BrokenPipe) if the consumer is gone.
+    pub fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        match self.poll_ready(cx) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+            Poll::Pending => return Poll::Pending,
+        }
+
+        let written = self.inner.push_slice(buf);
+        self.signals.consumer.wake();
+        Poll::Ready(Ok(written))
+    }
+}
+
+impl Consumer<u8> {
+    /// Read as many bytes as are in the ring, up to the size of `buf`.
+    ///
+    /// Pending while the ring is empty, and reads zero bytes (end of file)
+    /// once the ring is empty and the producer is closed or gone.
+    pub fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let read = self.read_now(buf);
+        if read > 0 {
+            return Poll::Ready(Ok(read));
+        }
+
+        self.signals.consumer.register(cx.waker());
+
+        let closed = self.signals.producer.is_closed();
+        match self.read_now(buf) {
+            0 if !closed => Poll::Pending,
+            read => Poll::Ready(Ok(read)),
+        }
+    }
+
+    fn read_now(&mut self, buf: &mut [u8]) -> usize {
+        let read = self.inner.pop_slice(buf);
+        if read > 0 {
+            self.signals.producer.wake();
+        }
+        read
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for Producer<u8> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for Consumer<u8> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let read = match self.get_mut().poll_read(cx, buf.initialize_unfilled()) {
+            Poll::Ready(Ok(read)) => read,
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        buf.advance(read);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncWrite for Producer<u8> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncRead for Consumer<u8> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_read(cx, buf)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Duplex -
+// -----------------------------------------------------------------------------
+/// Create a pair of connected in-memory byte streams.
+///
+/// Bytes written to one end are read from the other. Each direction
+/// has its own ring of `capacity` bytes, and writing waits while it is full.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn duplex(capacity: usize) -> (DuplexStream, DuplexStream) {
+    let (a_write, b_read) = HorridRing::with_capacity(capacity).split_async();
+    let (b_write, a_read) = HorridRing::with_capacity(capacity).split_async();
+
+    let a = DuplexStream {
+        reader: a_read,
+        writer: a_write,
+    };
+
+    let b = DuplexStream {
+        reader: b_read,
+        writer: b_write,
+    };
+
+    (a, b)
+}
+
+/// One end of a [`duplex`] pipe.
+///
+/// Dropping or shutting down one end makes reads on the other end
+/// return end of file once they have caught up.
+pub struct DuplexStream {
+    reader: Consumer<u8>,
+    writer: Producer<u8>,
+}
+
+impl DuplexStream {
+    pub fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.reader.poll_read(cx, buf)
+    }
+
+    pub fn poll_write(&mut self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.writer.poll_write(cx, buf)
+    }
+
+    /// Close the writing direction. The other end can still write to this one.
+    pub fn close(&mut self) {
+        self.writer.close();
+    }
+
+    /// Split into the reading and writing halves.
+    pub fn into_split(self) -> (Consumer<u8>, Producer<u8>) {
+        (self.reader, self.writer)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for DuplexStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().reader).poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for DuplexStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncRead for DuplexStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl futures_io::AsyncWrite for DuplexStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::{FutureExt, SinkExt, StreamExt};
+    use std::thread;
+
+    #[test]
+    fn test_push_pop() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(2).split_async();
+        block_on(async {
+            producer.push(1).await.unwrap();
+            producer.push(2).await.unwrap();
+            assert_eq!(consumer.pop().await, Some(1));
+            assert_eq!(consumer.pop().await, Some(2));
+        });
+    }
+
+    #[test]
+    fn test_waits_across_threads() {
+        let (mut producer, consumer) = HorridRing::with_capacity(3).split_async();
+        let handle = thread::spawn(move || {
+            block_on(async {
+                for i in 0..1000 {
+                    producer.push(i).await.unwrap();
+                }
+            })
+        });
+
+        let received = block_on(consumer.collect::<Vec<_>>());
+        handle.join().unwrap();
+        assert_eq!(received, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sink_and_stream() {
+        let (mut producer, consumer) = HorridRing::with_capacity(2).split_async();
+        let handle = thread::spawn(move || {
+            block_on(async {
+                let mut values = futures::stream::iter((0..100).map(Ok));
+                producer.send_all(&mut values).await.unwrap();
+                SinkExt::close(&mut producer).await.unwrap();
+            })
+        });
+
+        let received = block_on(consumer.collect::<Vec<_>>());
+        handle.join().unwrap();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_cancelled_push() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(1).split_async();
+        block_on(producer.push(1)).unwrap();
+
+        // The ring is full, so this push is pending when it is dropped
+        assert!(producer.push(2).now_or_never().is_none());
+        assert_eq!(producer.len(), 1);
+
+        assert_eq!(consumer.try_pop(), Some(1));
+        assert_eq!(consumer.try_pop(), None);
+    }
+
+    #[test]
+    fn test_cancelled_pop() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(1).split_async();
+        assert!(consumer.pop().now_or_never().is_none());
+
+        producer.try_push(1).unwrap();
+        assert_eq!(block_on(consumer.pop()), Some(1));
+    }
+
+    #[test]
+    fn test_dropped_consumer_fails_push() {
+        let (mut producer, consumer) = HorridRing::with_capacity(1).split_async();
+        block_on(producer.push(1)).unwrap();
+
+        let handle = thread::spawn(move || block_on(producer.push(2)));
+        drop(consumer);
+        assert_eq!(handle.join().unwrap(), Err(2));
+    }
+
+    #[test]
+    fn test_closed_producer_ends_stream() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(2).split_async();
+        producer.try_push(1).unwrap();
+        producer.close();
+
+        assert_eq!(block_on(consumer.pop()), Some(1));
+        assert_eq!(block_on(consumer.pop()), None);
+    }
+
+    #[test]
+    fn test_push_after_close() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(2).split_async();
+        producer.close();
+        assert_eq!(producer.try_push(1), Err(1));
+        assert_eq!(block_on(producer.push(2)), Err(2));
+        assert_eq!(block_on(producer.send(3)), Err(HorridError::Disconnected));
+        assert_eq!(consumer.try_pop(), None);
+    }
+
+    #[test]
+    fn test_push_after_consumer_close() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(2).split_async();
+        consumer.close();
+        assert_eq!(producer.try_push(1), Err(1));
+        assert_eq!(Pin::new(&mut producer).start_send(2), Err(HorridError::Disconnected));
+        assert!(producer.is_empty());
+    }
+
+    #[test]
+    fn test_byte_pipe_wraps() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(4).split_async();
+        let mut buf = [0; 8];
+        block_on(async {
+            for chunk in [&b"abc"[..], b"defg", b"hi"] {
+                let written = poll_fn(|cx| producer.poll_write(cx, chunk)).await.unwrap();
+                assert_eq!(written, chunk.len());
+                let read = poll_fn(|cx| consumer.poll_read(cx, &mut buf)).await.unwrap();
+                assert_eq!(&buf[..read], chunk);
+            }
+        });
+    }
+
+    #[test]
+    fn test_full_write_is_pending() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(2).split_async();
+        block_on(poll_fn(|cx| producer.poll_write(cx, b"abc"))).unwrap();
+
+        assert!(poll_fn(|cx| producer.poll_write(cx, b"d")).now_or_never().is_none());
+
+        let mut buf = [0; 1];
+        block_on(poll_fn(|cx| consumer.poll_read(cx, &mut buf))).unwrap();
+        assert_eq!(block_on(poll_fn(|cx| producer.poll_write(cx, b"d"))).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_write_to_dropped_consumer() {
+        let (mut producer, consumer) = HorridRing::with_capacity(2).split_async();
+        drop(consumer);
+        let err = block_on(poll_fn(|cx| producer.poll_write(cx, b"a"))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio_duplex() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut client, mut server) = duplex(3);
+        let handle = thread::spawn(move || {
+            block_on(async {
+                let mut request = Vec::new();
+                server.read_to_end(&mut request).await.unwrap();
+                request.reverse();
+                server.write_all(&request).await.unwrap();
+            })
+        });
+
+        block_on(async {
+            client.write_all(b"hello world").await.unwrap();
+            client.shutdown().await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            assert_eq!(response, b"dlrow olleh");
+        });
+        handle.join().unwrap();
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn test_futures_io_duplex() {
+        use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut client, mut server) = duplex(3);
+        let handle = thread::spawn(move || {
+            block_on(async {
+                let mut request = Vec::new();
+                server.read_to_end(&mut request).await.unwrap();
+                request.reverse();
+                server.write_all(&request).await.unwrap();
+            })
+        });
+
+        block_on(async {
+            client.write_all(b"hello world").await.unwrap();
+            AsyncWriteExt::close(&mut client).await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            assert_eq!(response, b"dlrow olleh");
+        });
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+// Atomics shared by the lock-free rings, swapped for loom's when model checking.
+use core::ops::Deref;
+
+#[cfg(not(loom))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(not(loom))]
+pub(crate) use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::Arc;
+
+// -----------------------------------------------------------------------------
+//     - Cache padding -
+// -----------------------------------------------------------------------------
+// Keeps indices written by different threads on separate cache lines
+// so the threads don't fight over the same line.
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+// Wait for another thread to make progress.
+pub(crate) fn backoff() {
+    #[cfg(loom)]
+    loom::thread::yield_now();
+
+    #[cfg(all(not(loom), feature = "std"))]
+    std::thread::yield_now();
+
+    #[cfg(all(not(loom), not(feature = "std")))]
+    core::hint::spin_loop();
+}
@@ -0,0 +1,81 @@
+use core::fmt;
+
+// -----------------------------------------------------------------------------
+//     - Error -
+// -----------------------------------------------------------------------------
+/// Everything that can go wrong in a [`HorridRing`](crate::HorridRing).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HorridError {
+    /// A ring has to have room for at least one value.
+    ZeroCapacity,
+    /// The storage for the requested capacity would not fit in memory.
+    CapacityOverflow,
+    /// The allocator could not provide the storage.
+    AllocFailed,
+    /// The ring is full and the policy does not allow overwriting.
+    Full,
+    /// The other side of a channel is gone, or the channel was closed.
+    Disconnected,
+    /// Gave up waiting.
+    Timeout,
+    /// A call to the operating system failed with this error code.
+    Os(i32),
+    /// Shared memory did not start with a header for a ring of this type.
+    InvalidHeader,
+}
+
+impl fmt::Display for HorridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HorridError::ZeroCapacity => write!(f, "capacity must be at least one"),
+            HorridError::CapacityOverflow => write!(f, "capacity overflow"),
+            HorridError::AllocFailed => write!(f, "memory allocation failed"),
+            HorridError::Full => write!(f, "ring is full"),
+            HorridError::Disconnected => write!(f, "channel is disconnected"),
+            HorridError::Timeout => write!(f, "timed out"),
+            HorridError::Os(code) => write!(f, "os error {}", code),
+            HorridError::InvalidHeader => write!(f, "invalid ring header"),
+        }
+    }
+}
+
+impl core::error::Error for HorridError {}
+
+#[cfg(any(feature = "mirrored", feature = "shared", feature = "persistent"))]
+impl HorridError {
+    pub(crate) fn last_os_error() -> Self {
+        HorridError::Os(std::io::Error::last_os_error().raw_os_error().unwrap_or(0))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<HorridError> for std::io::Error {
+    fn from(err: HorridError) -> Self {
+        let kind = match err {
+            HorridError::ZeroCapacity | HorridError::CapacityOverflow => std::io::ErrorKind::InvalidInput,
+            HorridError::AllocFailed => std::io::ErrorKind::OutOfMemory,
+            HorridError::Full => std::io::ErrorKind::WriteZero,
+            HorridError::Disconnected => std::io::ErrorKind::BrokenPipe,
+            HorridError::Timeout => std::io::ErrorKind::TimedOut,
+            HorridError::Os(code) => return std::io::Error::from_raw_os_error(code),
+            HorridError::InvalidHeader => std::io::ErrorKind::InvalidData,
+        };
+
+        std::io::Error::new(kind, err)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for HorridError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            HorridError::ZeroCapacity | HorridError::CapacityOverflow => embedded_io::ErrorKind::InvalidInput,
+            HorridError::AllocFailed => embedded_io::ErrorKind::OutOfMemory,
+            HorridError::Full => embedded_io::ErrorKind::WriteZero,
+            HorridError::Disconnected => embedded_io::ErrorKind::BrokenPipe,
+            HorridError::Timeout => embedded_io::ErrorKind::TimedOut,
+            HorridError::Os(_) => embedded_io::ErrorKind::Other,
+            HorridError::InvalidHeader => embedded_io::ErrorKind::InvalidData,
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::ptr;
+
+use crate::{wrap_index, Heap, HorridRing, Storage};
+
+// -----------------------------------------------------------------------------
+//     - Iter -
+// -----------------------------------------------------------------------------
+/// Borrowing iterator over a [`HorridRing`], oldest to newest.
+pub struct Iter<'a, T> {
+    inner: *const T,
+    read: usize,
+    capacity: usize,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    // Iterates over the values in `range`, as offsets from the oldest value.
+    pub(crate) fn new<S: Storage<T>>(ring: &'a HorridRing<T, S>, range: Range<usize>) -> Self {
+        Self {
+            inner: ring.storage.as_ptr(),
+            read: ring.read,
+            capacity: ring.capacity(),
+            front: range.start,
+            back: range.end,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let slot = wrap_index(self.read + self.front, self.capacity);
+        self.front += 1;
+        unsafe { self.inner.add(slot).as_ref() }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        let slot = wrap_index(self.read + self.back, self.capacity);
+        unsafe { self.inner.add(slot).as_ref() }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+// Only hands out `&T`, same as `core::slice::Iter`
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+// -----------------------------------------------------------------------------
+//     - IterMut -
+// -----------------------------------------------------------------------------
+/// Mutably borrowing iterator over a [`HorridRing`], oldest to newest.
+pub struct IterMut<'a, T> {
+    inner: *mut T,
+    read: usize,
+    capacity: usize,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> IterMut<'a, T> {
+    // Iterates over the values in `range`, as offsets from the oldest value.
+    pub(crate) fn new<S: Storage<T>>(ring: &'a mut HorridRing<T, S>, range: Range<usize>) -> Self {
+        Self {
+            inner: ring.storage.as_mut_ptr(),
+            read: ring.read,
+            capacity: ring.capacity(),
+            front: range.start,
+            back: range.end,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        // Every slot is handed out at most once, so the borrows never alias.
+        let slot = wrap_index(self.read + self.front, self.capacity);
+        self.front += 1;
+        unsafe { self.inner.add(slot).as_mut() }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        let slot = wrap_index(self.read + self.back, self.capacity);
+        unsafe { self.inner.add(slot).as_mut() }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+// Hands out `&mut T`, same as `core::slice::IterMut`
+unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+
+// -----------------------------------------------------------------------------
+//     - Drain -
+// -----------------------------------------------------------------------------
+/// Iterator removing a range of values from a [`HorridRing`], oldest to newest.
+///
+/// Values the iterator doesn't get to are dropped along with it.
+pub struct Drain<'a, T, S: Storage<T> = Heap<T>> {
+    ring: &'a mut HorridRing<T, S>,
+    // Values still to be yielded, as offsets from the oldest value
+    front: usize,
+    back: usize,
+    // The range being drained, and the length of the ring before draining
+    start: usize,
+    end: usize,
+    len: usize,
+}
+
+impl<'a, T, S: Storage<T>> Drain<'a, T, S> {
+    pub(crate) fn new(ring: &'a mut HorridRing<T, S>, range: Range<usize>) -> Self {
+        let len = ring.len();
+
+        // Until the drain is dropped the ring ends where the range starts.
+        // Leaking the drain leaks the values from there on, but nothing worse.
+        ring.tail = ring.head.wrapping_add(range.start as u64);
+
+        Self {
+            ring,
+            front: range.start,
+            back: range.end,
+            start: range.start,
+            end: range.end,
+            len,
+        }
+    }
+}
+
+impl<T, S: Storage<T>> Iterator for Drain<'_, T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let slot = self.ring.slot(self.front);
+        self.front += 1;
+        Some(unsafe { ptr::read(self.ring.storage.as_ptr().add(slot)) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T, S: Storage<T>> DoubleEndedIterator for Drain<'_, T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+        let slot = self.ring.slot(self.back);
+        Some(unsafe { ptr::read(self.ring.storage.as_ptr().add(slot)) })
+    }
+}
+
+impl<T, S: Storage<T>> ExactSizeIterator for Drain<'_, T, S> {}
+
+impl<T, S: Storage<T>> Drop for Drain<'_, T, S> {
+    fn drop(&mut self) {
+        // Closes the gap even if dropping a value panics. The values left
+        // in the range are leaked then, but the ones after it are kept.
+        struct Guard<'r, 'a, T, S: Storage<T>>(&'r mut Drain<'a, T, S>);
+
+        impl<T, S: Storage<T>> Drop for Guard<'_, '_, T, S> {
+            fn drop(&mut self) {
+                let drain = &mut *self.0;
+                drain.ring.close_gap(drain.start, drain.end, drain.len);
+            }
+        }
+
+        let guard = Guard(self);
+        guard.0.for_each(drop);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - IntoIter -
+// -----------------------------------------------------------------------------
+/// Owning iterator over a [`HorridRing`], oldest to newest.
+pub struct IntoIter<T, S: Storage<T> = Heap<T>> {
+    ring: HorridRing<T, S>,
+}
+
+impl<T, S: Storage<T>> Iterator for IntoIter<T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ring.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.ring.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, S: Storage<T>> DoubleEndedIterator for IntoIter<T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.ring.pop_back()
+    }
+}
+
+impl<T, S: Storage<T>> ExactSizeIterator for IntoIter<T, S> {}
+
+// -----------------------------------------------------------------------------
+//     - IntoIterator impls -
+// -----------------------------------------------------------------------------
+impl<T, S: Storage<T>> IntoIterator for HorridRing<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { ring: self }
+    }
+}
+
+impl<'a, T, S: Storage<T>> IntoIterator for &'a HorridRing<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, S: Storage<T>> IntoIterator for &'a mut HorridRing<T, S> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
@@ -1,117 +1,863 @@
-use std::alloc::{dealloc, alloc, Layout};
-use std::io::{Read, Write, Result};
-use std::mem::{size_of, align_of};
-use std::ptr;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Bound, Index, IndexMut, Range, RangeBounds};
+use core::ptr;
+use core::slice;
+#[cfg(feature = "std")]
+use std::io::{BufRead, IoSlice, IoSliceMut, Read, Write, Result};
+
+#[cfg(feature = "alloc")]
+mod atomic;
+mod error;
+mod iter;
+#[cfg(all(any(feature = "shared", feature = "persistent"), unix))]
+mod mmap;
+mod storage;
+#[cfg(all(test, feature = "std"))]
+mod test_util;
+
+#[cfg(feature = "async")]
+pub mod async_ring;
+#[cfg(all(feature = "mirrored", target_os = "linux"))]
+mod mirrored;
+#[cfg(feature = "alloc")]
+pub mod mpmc;
+#[cfg(all(feature = "persistent", unix))]
+pub mod persistent;
+#[cfg(all(feature = "shared", unix))]
+pub mod shared;
+#[cfg(feature = "alloc")]
+pub mod spsc;
+#[cfg(feature = "std")]
+pub mod sync;
+
+pub use error::HorridError;
+pub use iter::{Drain, IntoIter, Iter, IterMut};
+#[cfg(all(feature = "mirrored", target_os = "linux"))]
+pub use mirrored::{Mirrored, MirroredRing};
+pub use storage::{Heap, Inline, Storage};
+
+// -----------------------------------------------------------------------------
+//     - Full policy -
+// -----------------------------------------------------------------------------
+/// What happens when pushing to a full ring.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum FullPolicy {
+    /// Evict the oldest value to make room for the new one.
+    #[default]
+    OverwriteOldest,
+    /// Refuse the new value and hand it back to the caller.
+    Reject,
+    /// Wait for a reader to make room.
+    ///
+    /// Only [`sync::channel_with_policy`] and [`HorridMpmc`](crate::mpmc::HorridMpmc)
+    /// ever wait. Pushing to a `HorridRing` with this policy behaves exactly
+    /// like `Reject`, and the halves returned by [`split`](HorridRing::split)
+    /// ignore the policy altogether.
+    Block,
+}
+
+// -----------------------------------------------------------------------------
+//     - Keep -
+// -----------------------------------------------------------------------------
+/// Which values stay when [`resize`](HorridRing::resize) shrinks a ring
+/// below the number of values in it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Keep {
+    /// Keep the newest values and drop the oldest.
+    Newest,
+    /// Keep the oldest values and drop the newest.
+    Oldest,
+}
 
 // -----------------------------------------------------------------------------
 //     - Ring buffer -
 // -----------------------------------------------------------------------------
-pub struct HorridRing<T> {
+/// Fixed capacity ring buffer.
+///
+/// Every value pushed is given a sequence number, starting at zero and
+/// increasing by one per push. The oldest value in the ring is at
+/// [`head_seq`](Self::head_seq) and the next value pushed will be given
+/// [`tail_seq`](Self::tail_seq).
+///
+/// The values live in the [`Storage`] `S`, which is on the heap by default.
+/// See [`InlineRing`] for a ring that keeps its values inline.
+pub struct HorridRing<T, S: Storage<T> = Heap<T>> {
     read: usize,
-    write: usize,
-    write_wrap: u8,
-    read_wrap: u8,
-    inner: *mut T,
-    capacity: usize,
+    head: u64,
+    tail: u64,
+    storage: S,
+    policy: FullPolicy,
+    // The ring owns and drops its values, the storage only holds them
+    _marker: PhantomData<T>,
 }
 
+/// A [`HorridRing`] with room for `N` values inside the struct itself.
+pub type InlineRing<T, const N: usize> = HorridRing<T, Inline<T, N>>;
+
+#[cfg(feature = "alloc")]
 impl<T> HorridRing<T> {
+    /// Capacity of a ring made with [`Default`] or [`FromIterator`].
+    pub const DEFAULT_CAPACITY: usize = 16;
+
+    /// # Panics
+    ///
+    /// Panics if [`try_with_capacity`](Self::try_with_capacity) would fail.
     pub fn with_capacity(capacity: usize) -> Self {
-        let layout = Layout::from_size_align(capacity * size_of::<T>(), align_of::<T>())
-            .expect("could not layout");
+        Self::with_policy(capacity, FullPolicy::default())
+    }
 
-        let mem = unsafe { alloc(layout) };
-        let inner = mem.cast::<T>();
+    /// # Panics
+    ///
+    /// Panics if [`try_with_policy`](Self::try_with_policy) would fail.
+    pub fn with_policy(capacity: usize, policy: FullPolicy) -> Self {
+        match Self::try_with_policy(capacity, policy) {
+            Ok(ring) => ring,
+            Err(e) => panic!("could not create ring: {}", e),
+        }
+    }
 
-        Self {
+    pub fn try_with_capacity(capacity: usize) -> core::result::Result<Self, HorridError> {
+        Self::try_with_policy(capacity, FullPolicy::default())
+    }
+
+    /// Fails if `capacity` is zero, if the storage would not fit in memory,
+    /// or if the allocation fails.
+    pub fn try_with_policy(capacity: usize, policy: FullPolicy) -> core::result::Result<Self, HorridError> {
+        let ring = Self {
             read: 0,
-            write: 0,
-            write_wrap: 0,
-            read_wrap: 0,
-            inner,
-            capacity,
+            head: 0,
+            tail: 0,
+            storage: Heap::try_with_capacity(capacity)?,
+            policy,
+            _marker: PhantomData,
+        };
+
+        Ok(ring)
+    }
+
+    /// Change the capacity, keeping the values in order.
+    ///
+    /// If the values don't all fit, `keep` picks which ones stay and the
+    /// others are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_resize`](Self::try_resize) would fail.
+    pub fn resize(&mut self, new_capacity: usize, keep: Keep) {
+        if let Err(e) = self.try_resize(new_capacity, keep) {
+            panic!("could not resize ring: {}", e);
         }
     }
 
-    pub fn push(&mut self, val: T) {
+    /// Fails if `new_capacity` is zero, if the storage would not fit in
+    /// memory, or if the allocation fails. The ring is left as it was.
+    pub fn try_resize(&mut self, new_capacity: usize, keep: Keep) -> core::result::Result<(), HorridError> {
+        if new_capacity == self.capacity() {
+            return Ok(());
+        }
+
+        let mut storage = Heap::try_with_capacity(new_capacity)?;
+
+        match keep {
+            Keep::Newest => self.truncate_front(new_capacity),
+            Keep::Oldest => self.truncate_back(new_capacity),
+        }
+
+        // Move the values to the start of the new storage, oldest first
+        let (front, back) = self.slice_lens();
         unsafe {
-            ptr::write(self.inner.offset(self.write as isize), val);
-            self.write = (self.write + 1) % self.capacity;
-            if self.write == 0 {
-                self.write_wrap = self.write_wrap.wrapping_add(1);
+            let src = self.storage.as_ptr();
+            let dst = storage.as_mut_ptr();
+            ptr::copy_nonoverlapping(src.add(self.read), dst, front);
+            ptr::copy_nonoverlapping(src, dst.add(front), back);
+        }
+
+        // The old storage only frees its memory, the values have moved
+        self.storage = storage;
+        self.read = 0;
+        Ok(())
+    }
+
+    /// Make room for at least `additional` more values than are in the ring.
+    ///
+    /// Unlike [`Vec::reserve`](alloc::vec::Vec::reserve) this grows to exactly what was asked for,
+    /// as the capacity decides how many values an overwriting ring keeps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_reserve`](Self::try_reserve) would fail.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(e) = self.try_reserve(additional) {
+            panic!("could not resize ring: {}", e);
+        }
+    }
+
+    pub fn try_reserve(&mut self, additional: usize) -> core::result::Result<(), HorridError> {
+        let needed = self.len().checked_add(additional).ok_or(HorridError::CapacityOverflow)?;
+        if needed <= self.capacity() {
+            return Ok(());
+        }
+
+        // Growing never drops anything, so either side will do
+        self.try_resize(needed, Keep::Newest)
+    }
+
+    /// Shrink the capacity to the number of values, or to one if the ring is empty.
+    pub fn shrink_to_fit(&mut self) {
+        self.resize(self.len().max(1), Keep::Newest);
+    }
+
+    /// Split the ring in two at `at`, counted from the oldest value.
+    ///
+    /// The returned ring holds the values from `at` on, with the same
+    /// capacity and policy, and the values keep their sequence numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than [`len`](Self::len).
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "`at` {} out of range for ring of length {}", at, self.len());
+
+        let mut other = Self::with_policy(self.capacity(), self.policy);
+        other.head = self.head.wrapping_add(at as u64);
+        other.tail = other.head;
+        other.extend(self.drain(at..));
+        other
+    }
+}
+
+impl<T, const N: usize> InlineRing<T, N> {
+    /// A zero sized inline ring fails to compile.
+    pub const fn new() -> Self {
+        Self {
+            read: 0,
+            head: 0,
+            tail: 0,
+            storage: Inline::new(),
+            policy: FullPolicy::OverwriteOldest,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize> Default for InlineRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S: Storage<T>> HorridRing<T, S> {
+    pub fn policy(&self) -> FullPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: FullPolicy) {
+        self.policy = policy;
+    }
+
+    /// The number of values in the ring.
+    pub fn len(&self) -> usize {
+        // Sequence numbers wrap, as `push_front` can take the head below zero
+        self.tail.wrapping_sub(self.head) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True if the next push has to evict a value or be rejected.
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// The number of values the ring can hold.
+    pub fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    /// Push a value, applying the [`FullPolicy`] if the ring is full.
+    ///
+    /// With `OverwriteOldest` this returns the evicted value, if any.
+    /// With `Reject` or `Block` this returns `val` if there was no room for it.
+    pub fn push(&mut self, val: T) -> Option<T> {
+        match self.policy {
+            FullPolicy::OverwriteOldest => self.push_overwrite(val),
+            FullPolicy::Reject | FullPolicy::Block => self.try_push(val).err(),
+        }
+    }
+
+    /// Push a value if there is room for it, regardless of policy.
+    pub fn try_push(&mut self, val: T) -> core::result::Result<(), T> {
+        if self.is_full() {
+            return Err(val);
+        }
+
+        self.write_back(val);
+        Ok(())
+    }
+
+    /// Push a value in front of the oldest value, applying the [`FullPolicy`]
+    /// if the ring is full.
+    ///
+    /// With `OverwriteOldest` the newest value is evicted to make room, since
+    /// the value pushed becomes the oldest. It is given the sequence number
+    /// `head_seq() - 1`.
+    pub fn push_front(&mut self, val: T) -> Option<T> {
+        match self.policy {
+            FullPolicy::OverwriteOldest => {
+                let evicted = if self.is_full() {
+                    self.pop_back()
+                } else {
+                    None
+                };
+
+                self.write_front(val);
+                evicted
             }
+            FullPolicy::Reject | FullPolicy::Block => self.try_push_front(val).err(),
+        }
+    }
+
+    /// Push a value in front of the oldest value if there is room for it,
+    /// regardless of policy.
+    pub fn try_push_front(&mut self, val: T) -> core::result::Result<(), T> {
+        if self.is_full() {
+            return Err(val);
         }
+
+        self.write_front(val);
+        Ok(())
+    }
+
+    /// Push copies of the values in `src`, oldest first, applying the
+    /// [`FullPolicy`] if there is not room for all of them.
+    ///
+    /// With `OverwriteOldest` every value is pushed, evicting as many of
+    /// the oldest values as needed, and this returns `src.len()`.
+    /// With `Reject` or `Block` only as many values as there is room for
+    /// are pushed, and this returns how many that was.
+    pub fn push_slice(&mut self, src: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let free = self.capacity() - self.len();
+        let src = match self.policy {
+            FullPolicy::OverwriteOldest if src.len() >= self.capacity() => {
+                // Everything in the ring is evicted, as are all but the last
+                // `capacity` values of `src`.
+                let skipped = src.len() - self.capacity();
+                self.head = self.tail.wrapping_add(skipped as u64);
+                self.tail = self.head;
+                self.read = 0;
+                self.copy_back(&src[skipped..]);
+                return src.len();
+            }
+            FullPolicy::OverwriteOldest => {
+                let evict = src.len().saturating_sub(free);
+                self.advance_read(evict);
+                src
+            }
+            FullPolicy::Reject | FullPolicy::Block => &src[..src.len().min(free)],
+        };
+
+        self.copy_back(src);
+        src.len()
+    }
+
+    /// Pop the oldest values into `dst`, returning how many were popped.
+    pub fn pop_slice(&mut self, dst: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let (front, back) = self.as_slices();
+        let len = dst.len().min(front.len() + back.len());
+
+        // At most two copies: up to the end of the storage, then from the start
+        let first = len.min(front.len());
+        dst[..first].copy_from_slice(&front[..first]);
+        dst[first..len].copy_from_slice(&back[..len - first]);
+
+        self.advance_read(len);
+        len
     }
 
     pub fn clear(&mut self) {
-        self.write = 0;
-        self.read = 0;
-        self.write_wrap = 0;
-        self.read_wrap = 0;
+        while self.pop().is_some() {}
     }
 
-    pub fn drain(&mut self) -> Vec<T> {
-        let ret_val = self.collect::<Vec<_>>();
-        self.clear();
-        ret_val
+    /// Remove the values in `range`, counted from the oldest value,
+    /// and iterate over them. Values the iterator doesn't get to are
+    /// dropped along with it.
+    ///
+    /// Whichever side of the range holds fewer values is moved to close
+    /// the gap, and sequence numbers follow positions. So values before
+    /// the range keep their sequence numbers if the values after it moved,
+    /// and the values after it keep theirs otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range starts after it ends, or ends after [`len`](Self::len).
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, S> {
+        let range = self.range_offsets(range);
+        Drain::new(self, range)
     }
-}
 
-// -----------------------------------------------------------------------------
-//     - Iterator impl -
-// -----------------------------------------------------------------------------
-impl<T> Iterator for HorridRing<T> {
-    type Item = T;
+    /// Keep the newest `len` values and drop the rest.
+    /// Does nothing if there are no more than `len` values.
+    pub fn truncate_front(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop();
+        }
+    }
+
+    /// Keep the oldest `len` values and drop the rest.
+    /// Does nothing if there are no more than `len` values.
+    pub fn truncate_back(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_back();
+        }
+    }
+
+    /// Move the values out of `other` to the back of this ring, oldest first,
+    /// applying the [`FullPolicy`].
+    ///
+    /// With `OverwriteOldest` every value is moved, evicting and dropping
+    /// the oldest values as needed. With `Reject` or `Block` only as many
+    /// values are moved as there is room for, and the rest stay in `other`.
+    pub fn append<S2: Storage<T>>(&mut self, other: &mut HorridRing<T, S2>) {
+        let count = match self.policy {
+            FullPolicy::OverwriteOldest => other.len(),
+            FullPolicy::Reject | FullPolicy::Block => other.len().min(self.capacity() - self.len()),
+        };
+
+        self.extend(other.drain(..count));
+    }
+
+    /// Remove and return the oldest value.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let p = unsafe { self.storage.as_mut_ptr().add(self.read).read() };
+        self.advance_read(1);
+        Some(p)
+    }
+
+    /// Remove and return the newest value.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let back = self.slot(self.len() - 1);
+        let p = unsafe { self.storage.as_mut_ptr().add(back).read() };
+        self.tail = self.tail.wrapping_sub(1);
+        Some(p)
+    }
+
+    /// The oldest value.
+    pub fn front(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    /// The newest value.
+    pub fn back(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.iter_mut().next()
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.iter_mut().next_back()
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.read_wrap == self.write_wrap && self.read == self.write {
+    /// The value `index` places after the oldest, so `get(0)` is the oldest.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
             return None;
         }
 
-        let p = if self.read_wrap < self.write_wrap && self.read < self.write {
-            self.read = self.write;
-            let p = unsafe { self.inner.offset(self.read as isize).read() };
-            p
+        Some(unsafe { self.get_unchecked(index) })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        Some(unsafe { self.get_unchecked_mut(index) })
+    }
+
+    /// The value `index` places before the newest, so `get_back(0)` is the newest.
+    pub fn get_back(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        Some(unsafe { self.get_unchecked(self.len() - 1 - index) })
+    }
+
+    pub fn get_back_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let index = self.len() - 1 - index;
+        Some(unsafe { self.get_unchecked_mut(index) })
+    }
+
+    /// [`get`](Self::get) without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than [`len`](Self::len).
+    pub unsafe fn get_unchecked(&self, index: usize) -> &T {
+        &*self.storage.as_ptr().add(self.slot(index))
+    }
+
+    /// [`get_mut`](Self::get_mut) without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than [`len`](Self::len).
+    pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut T {
+        let slot = self.slot(index);
+        &mut *self.storage.as_mut_ptr().add(slot)
+    }
+
+    /// Iterate over the values, oldest to newest, without removing them.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self, 0..self.len())
+    }
+
+    /// Iterate over the values in `range`, counted from the oldest value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range starts after it ends, or ends after [`len`](Self::len).
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Iter<'_, T> {
+        Iter::new(self, self.range_offsets(range))
+    }
+
+    /// Mutable version of [`range`](Self::range).
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> IterMut<'_, T> {
+        let range = self.range_offsets(range);
+        IterMut::new(self, range)
+    }
+
+    /// Mutably iterate over the values, oldest to newest, without removing them.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let len = self.len();
+        IterMut::new(self, 0..len)
+    }
+
+    /// The values as two slices, oldest to newest.
+    /// The second slice is empty unless the values wrap around the end of
+    /// the storage.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (front, back) = self.slice_lens();
+        unsafe {
+            (
+                slice::from_raw_parts(self.storage.as_ptr().add(self.read), front),
+                slice::from_raw_parts(self.storage.as_ptr(), back),
+            )
+        }
+    }
+
+    /// Mutable version of [`as_slices`](Self::as_slices).
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (front, back) = self.slice_lens();
+        let inner = self.storage.as_mut_ptr();
+        unsafe {
+            (
+                slice::from_raw_parts_mut(inner.add(self.read), front),
+                slice::from_raw_parts_mut(inner, back),
+            )
+        }
+    }
+
+    /// Rotate the storage in place so all values are in one slice,
+    /// oldest to newest.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        let (_, back) = self.slice_lens();
+        if back > 0 {
+            // Rotating `MaybeUninit`s is fine even though some slots are uninitialized
+            let storage = unsafe {
+                slice::from_raw_parts_mut(
+                    self.storage.as_mut_ptr().cast::<MaybeUninit<T>>(),
+                    self.capacity(),
+                )
+            };
+            storage.rotate_left(self.read);
+            self.read = 0;
+        }
+
+        self.as_mut_slices().0
+    }
+
+    /// Sequence number of the oldest value in the ring.
+    /// Equal to [`tail_seq`](Self::tail_seq) when the ring is empty.
+    ///
+    /// Sequence numbers wrap around, so a `push_front` at zero
+    /// gives the new value `u64::MAX`.
+    pub fn head_seq(&self) -> u64 {
+        self.head
+    }
+
+    /// Sequence number the next pushed value will be given.
+    pub fn tail_seq(&self) -> u64 {
+        self.tail
+    }
+
+    /// Get a value by its sequence number.
+    ///
+    /// Returns `None` if the value has been read or overwritten
+    /// (`seq < head_seq()`), or has not been pushed yet (`seq >= tail_seq()`).
+    pub fn get_by_seq(&self, seq: u64) -> Option<&T> {
+        let offset = seq.wrapping_sub(self.head);
+        if offset >= self.len() as u64 {
+            return None;
+        }
+
+        unsafe { self.storage.as_ptr().add(self.slot(offset as usize)).as_ref() }
+    }
+
+    fn push_overwrite(&mut self, val: T) -> Option<T> {
+        // Move the oldest value out before its slot is reused.
+        let evicted = if self.is_full() {
+            self.pop()
         } else {
-            let p = unsafe { self.inner.offset(self.read as isize).read() };
-            p
+            None
         };
 
-        self.read = (self.read + 1) % self.capacity;
-        if self.read == 0 {
-            self.read_wrap = self.read_wrap.wrapping_add(1);
+        self.write_back(val);
+        evicted
+    }
+
+    // Caller must make sure the ring is not full.
+    fn write_back(&mut self, val: T) {
+        let write = self.slot(self.len());
+        unsafe { ptr::write(self.storage.as_mut_ptr().add(write), val) };
+        self.tail = self.tail.wrapping_add(1);
+    }
+
+    // Caller must make sure there is room for all of `src`.
+    fn copy_back(&mut self, src: &[T])
+    where
+        T: Copy,
+    {
+        let write = self.slot(self.len());
+        let first = src.len().min(self.capacity() - write);
+        let inner = self.storage.as_mut_ptr();
+        unsafe {
+            ptr::copy_nonoverlapping(src.as_ptr(), inner.add(write), first);
+            ptr::copy_nonoverlapping(src.as_ptr().add(first), inner, src.len() - first);
         }
+        self.tail = self.tail.wrapping_add(src.len() as u64);
+    }
 
-        Some(p)
+    // Caller must make sure the ring is not full.
+    fn write_front(&mut self, val: T) {
+        self.read = self.slot(self.capacity() - 1);
+        self.head = self.head.wrapping_sub(1);
+        unsafe { ptr::write(self.storage.as_mut_ptr().add(self.read), val) };
+    }
+
+    // Offsets from the oldest value covered by `range`,
+    // panicking like slice indexing if it is out of bounds.
+    fn range_offsets<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.saturating_add(1),
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len(),
+        };
+
+        assert!(start <= end, "range starts at {} but ends at {}", start, end);
+        assert!(end <= self.len(), "range end {} out of range for ring of length {}", end, self.len());
+        start..end
+    }
+
+    // Close the gap left by draining `start..end` out of a ring that held
+    // `len` values, by moving the values on the shorter side of it.
+    // Until then the ring ends at `start`.
+    fn close_gap(&mut self, start: usize, end: usize, len: usize) {
+        let gap = end - start;
+        let after = len - end;
+        let inner = self.storage.as_mut_ptr();
+
+        if gap > 0 && start <= after {
+            // Move the values before the gap up to meet the values after it,
+            // newest first so none are overwritten before they are moved.
+            for offset in (0..start).rev() {
+                let (src, dst) = (self.slot(offset), self.slot(offset + gap));
+                unsafe { ptr::copy_nonoverlapping(inner.add(src), inner.add(dst), 1) };
+            }
+            self.advance_read(gap);
+        } else if gap > 0 {
+            for offset in 0..after {
+                let (src, dst) = (self.slot(end + offset), self.slot(start + offset));
+                unsafe { ptr::copy_nonoverlapping(inner.add(src), inner.add(dst), 1) };
+            }
+        }
+
+        self.tail = self.head.wrapping_add((len - gap) as u64);
+    }
+
+    // Length of the run from `read` to the end of the storage,
+    // and of the run that wrapped around to the start.
+    fn slice_lens(&self) -> (usize, usize) {
+        let front = self.len().min(self.capacity() - self.read);
+        (front, self.len() - front)
+    }
+
+    // Slot index of the value `offset` places after the oldest value.
+    fn slot(&self, offset: usize) -> usize {
+        wrap_index(self.read + offset, self.capacity())
+    }
+
+    // Skip over `count` values without dropping them.
+    fn advance_read(&mut self, count: usize) {
+        self.read = self.slot(count);
+        self.head = self.head.wrapping_add(count as u64);
+    }
+}
+
+// Wrap an index that has run past the end of the storage.
+// For inline storage the capacity is a constant, so the branch goes away.
+fn wrap_index(index: usize, capacity: usize) -> usize {
+    if capacity.is_power_of_two() {
+        index & (capacity - 1)
+    } else {
+        index % capacity
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Byte ring -
+// -----------------------------------------------------------------------------
+// Shared by the `std::io` and `embedded-io` impls.
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+impl<S: Storage<u8>> HorridRing<u8, S> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> usize {
+        self.pop_slice(buf)
+    }
+
+    fn write_bytes(&mut self, buf: &[u8]) -> usize {
+        self.push_slice(buf)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Transfers -
+// -----------------------------------------------------------------------------
+#[cfg(feature = "std")]
+impl<S: Storage<u8>> HorridRing<u8, S> {
+    /// Read from `reader` straight into the free space of the ring,
+    /// with a single call to [`Read::read_vectored`].
+    ///
+    /// Never overwrites, whatever the policy. Returns `Ok(0)` only if
+    /// `reader` is at end of file, and fails with
+    /// [`ErrorKind::WriteZero`](std::io::ErrorKind::WriteZero) without
+    /// reading if the ring is full.
+    pub fn fill_from<R: Read>(&mut self, mut reader: R) -> Result<usize> {
+        let free = self.capacity() - self.len();
+        if free == 0 {
+            return Err(HorridError::Full.into());
+        }
+
+        let write = self.slot(self.len());
+        let first = free.min(self.capacity() - write);
+        let inner = self.storage.as_mut_ptr();
+
+        // Storage starts out zeroed and bytes stay initialized once written,
+        // so the free space can be handed out as slices as it is.
+        let read = unsafe {
+            let mut bufs = [
+                IoSliceMut::new(slice::from_raw_parts_mut(inner.add(write), first)),
+                IoSliceMut::new(slice::from_raw_parts_mut(inner, free - first)),
+            ];
+            reader.read_vectored(&mut bufs)?
+        };
+
+        // Don't trust the reader to stay within the buffers it was given
+        let read = read.min(free);
+        self.tail = self.tail.wrapping_add(read as u64);
+        Ok(read)
+    }
+
+    /// Write the bytes in the ring straight to `writer`, with a single
+    /// call to [`Write::write_vectored`], and remove the bytes written.
+    pub fn drain_to<W: Write>(&mut self, mut writer: W) -> Result<usize> {
+        let (front, back) = self.as_slices();
+        let len = front.len() + back.len();
+        let written = writer.write_vectored(&[IoSlice::new(front), IoSlice::new(back)])?;
+
+        let written = written.min(len);
+        self.advance_read(written);
+        Ok(written)
     }
 }
 
 // -----------------------------------------------------------------------------
 //     - Read impl -
 // -----------------------------------------------------------------------------
-impl Read for HorridRing<u8> {
+#[cfg(feature = "std")]
+impl<S: Storage<u8>> Read for HorridRing<u8, S> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        let mut index = 0;
-        let buf_len = buf.len();
-        while let Some(val) = self.next() {
-            buf[index] = val;
-            index += 1;
-            if index == buf_len {
+        Ok(self.read_bytes(buf))
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize> {
+        let mut read = 0;
+        for buf in bufs {
+            read += self.read_bytes(buf);
+            if self.is_empty() {
                 break;
             }
         }
 
-        Ok(index)
+        Ok(read)
     }
 }
 
 // -----------------------------------------------------------------------------
 //     - Write impl -
 // -----------------------------------------------------------------------------
-impl Write for HorridRing<u8> {
+#[cfg(feature = "std")]
+impl<S: Storage<u8>> Write for HorridRing<u8, S> {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        buf.iter().for_each(|b| self.push(*b));
-        Ok(buf.len())
+        Ok(self.write_bytes(buf))
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        let mut written = 0;
+        for buf in bufs {
+            let len = self.write_bytes(buf);
+            written += len;
+            if len < buf.len() {
+                break;
+            }
+        }
+
+        Ok(written)
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -120,37 +866,233 @@ impl Write for HorridRing<u8> {
 }
 
 // -----------------------------------------------------------------------------
-//     - Drop impl -
+//     - BufRead impl -
 // -----------------------------------------------------------------------------
+#[cfg(feature = "std")]
+impl<S: Storage<u8>> BufRead for HorridRing<u8, S> {
+    /// The bytes from the oldest up to the end of the storage.
+    /// If the bytes wrap around, the rest is returned once these are consumed.
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        Ok(self.as_slices().0)
+    }
 
-impl<T> Drop for HorridRing<T> {
-    fn drop(&mut self) {
-        unsafe {
-            let layout = Layout::from_size_align(self.capacity * size_of::<T>(), align_of::<T>()) .expect("could not layout");
-            dealloc(self.inner.cast::<u8>(), layout);
-        }
+    fn consume(&mut self, amt: usize) {
+        self.advance_read(amt.min(self.len()));
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use std::io::Read;
+// -----------------------------------------------------------------------------
+//     - embedded-io impls -
+// -----------------------------------------------------------------------------
+#[cfg(feature = "embedded-io")]
+impl<S: Storage<u8>> embedded_io::ErrorType for HorridRing<u8, S> {
+    type Error = HorridError;
+}
 
-    #[test]
-    fn test_read_empty() {
-        let mut rb = HorridRing::<u8>::with_capacity(4);
-        assert!(rb.next().is_none());
+#[cfg(feature = "embedded-io")]
+impl<S: Storage<u8>> embedded_io::Read for HorridRing<u8, S> {
+    /// Never blocks: an empty ring reads as end of file.
+    fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
+        Ok(self.read_bytes(buf))
     }
+}
 
-    #[test]
-    fn test_write_wraps() {
-        let mut rb = HorridRing::with_capacity(2);
-        rb.push(0); // 0 [0] [?]
-        rb.push(1); // 1 [0] [1]
-        rb.push(2); // 2 [2] [1]
-        assert_eq!(rb.next(), Some(1));
-        assert_eq!(rb.next(), Some(2));
+#[cfg(feature = "embedded-io")]
+impl<S: Storage<u8>> embedded_io::ReadReady for HorridRing<u8, S> {
+    fn read_ready(&mut self) -> core::result::Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<S: Storage<u8>> embedded_io::Write for HorridRing<u8, S> {
+    /// Never blocks: if the policy keeps the ring from taking any bytes
+    /// this fails with [`HorridError::Full`].
+    fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Self::Error> {
+        match self.write_bytes(buf) {
+            0 if !buf.is_empty() => Err(HorridError::Full),
+            len => Ok(len),
+        }
+    }
+
+    fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<S: Storage<u8>> embedded_io::WriteReady for HorridRing<u8, S> {
+    fn write_ready(&mut self) -> core::result::Result<bool, Self::Error> {
+        Ok(self.policy == FullPolicy::OverwriteOldest || self.len() < self.capacity())
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Std trait impls -
+// -----------------------------------------------------------------------------
+// Comparing, hashing and printing go by the values, oldest to newest,
+// no matter where in the storage they are.
+
+#[cfg(feature = "alloc")]
+impl<T> Default for HorridRing<T> {
+    /// An empty ring with room for [`DEFAULT_CAPACITY`](Self::DEFAULT_CAPACITY) values.
+    fn default() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// Keeps the sequence numbers, capacity and policy.
+#[cfg(feature = "alloc")]
+impl<T: Clone> Clone for HorridRing<T> {
+    fn clone(&self) -> Self {
+        let mut ring = Self::with_policy(self.capacity(), self.policy);
+        ring.clone_values_from(self);
+        ring
+    }
+}
+
+/// Keeps the sequence numbers and policy.
+impl<T: Clone, const N: usize> Clone for InlineRing<T, N> {
+    fn clone(&self) -> Self {
+        let mut ring = Self::new();
+        ring.policy = self.policy;
+        ring.clone_values_from(self);
+        ring
+    }
+}
+
+impl<T, S: Storage<T>> HorridRing<T, S> {
+    // Clone the values of `other` into this empty ring, keeping their sequence numbers.
+    fn clone_values_from<S2: Storage<T>>(&mut self, other: &HorridRing<T, S2>)
+    where
+        T: Clone,
+    {
+        self.head = other.head;
+        self.tail = other.head;
+        for val in other {
+            self.write_back(val.clone());
+        }
+    }
+}
+
+impl<T: fmt::Debug, S: Storage<T>> fmt::Debug for HorridRing<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+/// Rings are equal if they hold equal values in the same order,
+/// whatever their capacity, policy or sequence numbers.
+impl<T, S, S2> PartialEq<HorridRing<T, S2>> for HorridRing<T, S>
+where
+    T: PartialEq,
+    S: Storage<T>,
+    S2: Storage<T>,
+{
+    fn eq(&self, other: &HorridRing<T, S2>) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, S: Storage<T>> Eq for HorridRing<T, S> {}
+
+impl<T: Hash, S: Storage<T>> Hash for HorridRing<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|val| val.hash(state));
+    }
+}
+
+/// Indexes from the oldest value, like [`get`](HorridRing::get).
+impl<T, S: Storage<T>> Index<usize> for HorridRing<T, S> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(val) => val,
+            None => panic!("index {} out of range for ring of length {}", index, self.len()),
+        }
+    }
+}
+
+impl<T, S: Storage<T>> IndexMut<usize> for HorridRing<T, S> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len();
+        match self.get_mut(index) {
+            Some(val) => val,
+            None => panic!("index {} out of range for ring of length {}", index, len),
+        }
+    }
+}
+
+/// Pushes every value, applying the [`FullPolicy`].
+impl<T, S: Storage<T>> Extend<T> for HorridRing<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, S: Storage<T>> Extend<&'a T> for HorridRing<T, S> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+/// Keeps the last [`DEFAULT_CAPACITY`](Self::DEFAULT_CAPACITY) values.
+#[cfg(feature = "alloc")]
+impl<T> FromIterator<T> for HorridRing<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ring = Self::default();
+        ring.extend(iter);
+        ring
+    }
+}
+
+/// Keeps the last `N` values.
+impl<T, const N: usize> FromIterator<T> for InlineRing<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut ring = Self::new();
+        ring.extend(iter);
+        ring
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Drop impl -
+// -----------------------------------------------------------------------------
+
+impl<T, S: Storage<T>> Drop for HorridRing<T, S> {
+    fn drop(&mut self) {
+        // The storage frees its own memory
+        self.clear();
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+    use std::io::Read;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_read_empty() {
+        let mut rb = HorridRing::<u8>::with_capacity(4);
+        assert!(rb.pop().is_none());
+    }
+
+    #[test]
+    fn test_write_wraps() {
+        let mut rb = HorridRing::with_capacity(2);
+        rb.push(0); // 0 [0] [?]
+        rb.push(1); // 1 [0] [1]
+        rb.push(2); // 2 [2] [1]
+        assert_eq!(rb.pop(), Some(1));
+        assert_eq!(rb.pop(), Some(2));
     }
 
     #[test]
@@ -158,8 +1100,8 @@ mod test {
         let mut rb = HorridRing::with_capacity(2);
         rb.push(0);
         rb.push(1);
-        assert_eq!(rb.next(), Some(0));
-        assert_eq!(rb.next(), Some(1));
+        assert_eq!(rb.pop(), Some(0));
+        assert_eq!(rb.pop(), Some(1));
     }
 
     #[test]
@@ -182,7 +1124,7 @@ mod test {
 
         let bytes_written = rb.write(&buf).unwrap();
         assert_eq!(bytes_written, buf.len());
-        assert_eq!(rb.drain(), vec![3, 3]);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![3, 3]);
     }
 
     #[test]
@@ -191,7 +1133,7 @@ mod test {
         rb.push(1);
         rb.clear();
 
-        assert!(rb.next().is_none());
+        assert!(rb.pop().is_none());
     }
 
     #[test]
@@ -199,8 +1141,1031 @@ mod test {
         let mut rb = HorridRing::with_capacity(4);
         rb.push(1);
         rb.push(2);
-        let val = rb.drain();
+        let val = rb.drain(..).collect::<Vec<_>>();
 
         assert_eq!(val, vec![1, 2]);
     }
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_overwrite_drops_oldest() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(2);
+        rb.push(DropCounter(drops.clone()));
+        rb.push(DropCounter(drops.clone()));
+        assert_eq!(drops.get(), 0);
+
+        rb.push(DropCounter(drops.clone()));
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn test_clear_drops() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(4);
+        rb.push(DropCounter(drops.clone()));
+        rb.push(DropCounter(drops.clone()));
+        rb.push(DropCounter(drops.clone()));
+        rb.clear();
+
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn test_drop_remaining() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(3);
+        for _ in 0..5 {
+            rb.push(DropCounter(drops.clone()));
+        }
+        assert_eq!(drops.get(), 2);
+
+        drop(rb.pop());
+        assert_eq!(drops.get(), 3);
+
+        drop(rb);
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn test_drain_drops_once() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(4);
+        rb.push(DropCounter(drops.clone()));
+        rb.push(DropCounter(drops.clone()));
+
+        let vals = rb.drain(..).collect::<Vec<_>>();
+        assert_eq!(drops.get(), 0);
+
+        drop(vals);
+        drop(rb);
+        assert_eq!(drops.get(), 2);
+    }
+
+    #[test]
+    fn test_many_wraps() {
+        let mut rb = HorridRing::with_capacity(3);
+        for i in 0..1000 {
+            rb.push(i);
+        }
+
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![997, 998, 999]);
+
+        rb.push(1000);
+        rb.push(1001);
+        assert_eq!(rb.pop(), Some(1000));
+        assert_eq!(rb.pop(), Some(1001));
+        assert!(rb.pop().is_none());
+    }
+
+    #[test]
+    fn test_seq() {
+        let mut rb = HorridRing::with_capacity(2);
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (0, 0));
+
+        rb.push("a");
+        rb.push("b");
+        rb.push("c");
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (1, 3));
+
+        assert_eq!(rb.get_by_seq(0), None);
+        assert_eq!(rb.get_by_seq(1), Some(&"b"));
+        assert_eq!(rb.get_by_seq(2), Some(&"c"));
+        assert_eq!(rb.get_by_seq(3), None);
+
+        rb.pop();
+        assert_eq!(rb.get_by_seq(1), None);
+        assert_eq!(rb.get_by_seq(2), Some(&"c"));
+
+        rb.clear();
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (3, 3));
+    }
+
+    #[test]
+    fn test_push_returns_evicted() {
+        let mut rb = HorridRing::with_capacity(2);
+        assert_eq!(rb.push(1), None);
+        assert_eq!(rb.push(2), None);
+        assert_eq!(rb.push(3), Some(1));
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_reject_policy() {
+        let mut rb = HorridRing::with_policy(2, FullPolicy::Reject);
+        assert_eq!(rb.push(1), None);
+        assert_eq!(rb.try_push(2), Ok(()));
+        assert_eq!(rb.push(3), Some(3));
+        assert_eq!(rb.try_push(4), Err(4));
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_try_push_ignores_policy() {
+        let mut rb = HorridRing::with_capacity(1);
+        assert_eq!(rb.try_push(1), Ok(()));
+        assert_eq!(rb.try_push(2), Err(2));
+        assert_eq!(rb.pop(), Some(1));
+    }
+
+    #[test]
+    fn test_iter_does_not_consume() {
+        let mut rb = HorridRing::with_capacity(3);
+        for i in 0..5 {
+            rb.push(i);
+        }
+
+        assert_eq!(rb.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+        assert_eq!((&rb).into_iter().count(), 3);
+        assert_eq!(rb.pop(), Some(2));
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut rb = HorridRing::with_capacity(3);
+        for i in 0..4 {
+            rb.push(i);
+        }
+
+        rb.iter_mut().for_each(|v| *v *= 10);
+        for v in &mut rb {
+            *v += 1;
+        }
+
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn test_iter_clone() {
+        let rb: HorridRing<_> = (0..4).collect();
+        let mut iter = rb.iter();
+        iter.next();
+
+        let copy = iter.clone();
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&1, &2, &3]);
+        assert_eq!(copy.rev().collect::<Vec<_>>(), vec![&3, &2, &1]);
+    }
+
+    #[test]
+    fn test_iter_send_sync() {
+        fn is_send_sync<T: Send + Sync>(_: &T) {}
+
+        let mut rb: HorridRing<u32> = HorridRing::default();
+        is_send_sync(&rb.iter());
+        is_send_sync(&rb.iter_mut());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(3);
+        for _ in 0..3 {
+            rb.push(DropCounter(drops.clone()));
+        }
+
+        let mut iter = rb.into_iter();
+        drop(iter.next());
+        assert_eq!(drops.get(), 1);
+
+        drop(iter);
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn test_as_slices() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.push(1);
+        rb.push(2);
+        assert_eq!(rb.as_slices(), (&[1, 2][..], &[][..]));
+
+        rb.push(3);
+        rb.push(4);
+        rb.push(5);
+        assert_eq!(rb.as_slices(), (&[2, 3, 4][..], &[5][..]));
+
+        let (front, back) = rb.as_mut_slices();
+        front[0] = 20;
+        back[0] = 50;
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![20, 3, 4, 50]);
+    }
+
+    #[test]
+    fn test_make_contiguous() {
+        let mut rb = HorridRing::with_capacity(5);
+        for i in 0..7 {
+            rb.push(i);
+        }
+        rb.pop();
+        assert_eq!(rb.as_slices(), (&[3, 4][..], &[5, 6][..]));
+
+        assert_eq!(rb.make_contiguous(), &[3, 4, 5, 6]);
+        assert_eq!(rb.as_slices(), (&[3, 4, 5, 6][..], &[][..]));
+        assert_eq!(rb.get_by_seq(5), Some(&5));
+
+        rb.push(7);
+        rb.push(8);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_make_contiguous_keeps_values_alive() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(3);
+        for _ in 0..5 {
+            rb.push(DropCounter(drops.clone()));
+        }
+        assert_eq!(drops.get(), 2);
+
+        assert_eq!(rb.make_contiguous().len(), 3);
+        drop(rb);
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn test_push_front_pop_back() {
+        let mut rb = HorridRing::with_capacity(3);
+        rb.push(2);
+        rb.push_front(1);
+        rb.push(3);
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!((rb.front(), rb.back()), (Some(&1), Some(&3)));
+
+        // Pushing at the front of a full ring evicts the newest value
+        assert_eq!(rb.push_front(0), Some(3));
+        assert_eq!(rb.pop_back(), Some(2));
+        assert_eq!(rb.pop_back(), Some(1));
+        assert_eq!(rb.pop_back(), Some(0));
+        assert_eq!(rb.pop_back(), None);
+    }
+
+    #[test]
+    fn test_push_front_seq() {
+        let mut rb = HorridRing::with_capacity(3);
+        rb.push_front("a");
+        assert_eq!(rb.head_seq(), u64::MAX);
+        assert_eq!(rb.get_by_seq(u64::MAX), Some(&"a"));
+
+        rb.push("b");
+        assert_eq!(rb.get_by_seq(0), Some(&"b"));
+        assert_eq!(rb.tail_seq(), 1);
+    }
+
+    #[test]
+    fn test_push_front_reject() {
+        let mut rb = HorridRing::with_policy(1, FullPolicy::Reject);
+        assert_eq!(rb.push_front(1), None);
+        assert_eq!(rb.push_front(2), Some(2));
+        assert_eq!(rb.try_push_front(3), Err(3));
+    }
+
+    #[test]
+    fn test_front_back_mut() {
+        let mut rb = HorridRing::with_capacity(2);
+        assert!(rb.front_mut().is_none());
+
+        rb.push(1);
+        rb.push(2);
+        rb.push(3);
+        *rb.front_mut().unwrap() = 20;
+        *rb.back_mut().unwrap() = 30;
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![20, 30]);
+    }
+
+    #[test]
+    fn test_rev_iter() {
+        let mut rb = HorridRing::with_capacity(3);
+        for i in 0..5 {
+            rb.push(i);
+        }
+
+        assert_eq!(rb.iter().rev().copied().collect::<Vec<_>>(), vec![4, 3, 2]);
+
+        let mut iter = rb.iter_mut();
+        assert_eq!(iter.len(), 3);
+        *iter.next_back().unwrap() = 40;
+        assert_eq!(iter.len(), 2);
+        assert_eq!(rb.into_iter().rev().collect::<Vec<_>>(), vec![40, 3, 2]);
+    }
+
+    #[test]
+    fn test_inline() {
+        let mut rb = InlineRing::<_, 3>::new();
+        for i in 0..5 {
+            rb.push(i);
+        }
+
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(rb.as_slices(), (&[2][..], &[3, 4][..]));
+        assert_eq!(rb.pop_back(), Some(4));
+        assert_eq!(rb.into_iter().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_inline_power_of_two() {
+        let mut rb = InlineRing::<_, 4>::new();
+        for i in 0..10 {
+            rb.push(i);
+        }
+
+        assert_eq!(rb.get_by_seq(7), Some(&7));
+        assert_eq!(rb.make_contiguous(), &[6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_inline_drops() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = InlineRing::<_, 2>::new();
+        for _ in 0..3 {
+            rb.push(DropCounter(drops.clone()));
+        }
+        assert_eq!(drops.get(), 1);
+
+        drop(rb);
+        assert_eq!(drops.get(), 3);
+    }
+
+    #[test]
+    fn test_inline_static() {
+        static RING: Mutex<InlineRing<u32, 4>> = Mutex::new(InlineRing::new());
+
+        RING.lock().unwrap().push(1);
+        RING.lock().unwrap().push(2);
+        assert_eq!(RING.lock().unwrap().pop(), Some(1));
+    }
+
+    #[test]
+    fn test_try_with_capacity() {
+        assert!(HorridRing::<u8>::try_with_capacity(4).is_ok());
+        assert_eq!(HorridRing::<u8>::try_with_capacity(0).err(), Some(HorridError::ZeroCapacity));
+        assert_eq!(
+            HorridRing::<u64>::try_with_capacity(usize::MAX / 4).err(),
+            Some(HorridError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least one")]
+    fn test_zero_capacity_panics() {
+        HorridRing::<u8>::with_capacity(0);
+    }
+
+    #[test]
+    fn test_zst() {
+        let mut rb = HorridRing::with_capacity(3);
+        for _ in 0..5 {
+            rb.push(());
+        }
+
+        assert_eq!(rb.iter().count(), 3);
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (2, 5));
+        assert_eq!(rb.make_contiguous().len(), 3);
+        assert_eq!(rb.pop(), Some(()));
+        assert_eq!(rb.pop_back(), Some(()));
+        assert_eq!(rb.pop(), Some(()));
+        assert_eq!(rb.pop(), None);
+    }
+
+    #[test]
+    fn test_zst_huge_capacity() {
+        let mut rb = HorridRing::with_capacity(isize::MAX as usize);
+        rb.push(());
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![()]);
+
+        let rb = HorridRing::<()>::try_with_capacity(usize::MAX);
+        assert_eq!(rb.err(), Some(HorridError::CapacityOverflow));
+    }
+
+    #[test]
+    fn test_zst_drops() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Marker;
+
+        impl Drop for Marker {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut rb = HorridRing::with_policy(2, FullPolicy::Reject);
+        rb.push(Marker);
+        rb.push(Marker);
+        drop(rb.push(Marker));
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+        drop(rb.pop());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+        drop(rb);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn test_embedded_io() {
+        use embedded_io::{Read, Write, WriteReady};
+
+        let mut rb = InlineRing::<u8, 4>::new();
+        rb.set_policy(FullPolicy::Reject);
+        assert_eq!(Write::write(&mut rb, &[1, 2, 3, 4, 5]), Ok(4));
+        assert_eq!(rb.write_ready(), Ok(false));
+        assert_eq!(Write::write(&mut rb, &[6]), Err(HorridError::Full));
+
+        let mut buf = [0; 8];
+        assert_eq!(Read::read(&mut rb, &mut buf), Ok(4));
+        assert_eq!(&buf[..4], &[1, 2, 3, 4]);
+        assert_eq!(Read::read(&mut rb, &mut buf), Ok(0));
+    }
+
+    #[test]
+    fn test_write_respects_policy() {
+        let mut rb = HorridRing::with_policy(4, FullPolicy::Reject);
+        assert_eq!(rb.write(&[1, 2, 3]).unwrap(), 3);
+        assert_eq!(rb.write(&[4, 5, 6]).unwrap(), 1);
+        assert_eq!(rb.write(&[7]).unwrap(), 0);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_push_slice_matches_push() {
+        // Every split of the input, against a ring that was pushed one at a time
+        let values: Vec<u32> = (0..11).collect();
+        for start in 0..5 {
+            for split in 0..values.len() {
+                let mut bulk = HorridRing::with_capacity(5);
+                let mut single = HorridRing::with_capacity(5);
+                for i in 0..start {
+                    bulk.push(i);
+                    single.push(i);
+                }
+
+                assert_eq!(bulk.push_slice(&values[..split]), split);
+                assert_eq!(bulk.push_slice(&values[split..]), values.len() - split);
+                for &v in &values {
+                    single.push(v);
+                }
+
+                assert_eq!(bulk.head_seq(), single.head_seq());
+                assert_eq!(bulk.tail_seq(), single.tail_seq());
+                assert!(bulk.iter().eq(single.iter()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_slice_reject() {
+        let mut rb = HorridRing::with_policy(4, FullPolicy::Reject);
+        rb.push(0);
+        rb.pop();
+        assert_eq!(rb.push_slice(&[1, 2, 3, 4, 5]), 4);
+        assert_eq!(rb.push_slice(&[6]), 0);
+        assert_eq!(rb.tail_seq(), 5);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_pop_slice_wraps() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.push_slice(&[1, 2, 3, 4, 5, 6]);
+
+        let mut buf = [0; 3];
+        assert_eq!(rb.pop_slice(&mut buf), 3);
+        assert_eq!(buf, [3, 4, 5]);
+        assert_eq!(rb.head_seq(), 5);
+
+        assert_eq!(rb.pop_slice(&mut buf), 1);
+        assert_eq!(buf[0], 6);
+        assert_eq!(rb.pop_slice(&mut buf), 0);
+    }
+
+    #[test]
+    fn test_read_line_across_wrap() {
+        let mut rb = HorridRing::with_capacity(8);
+        rb.write_all(b"ab\ncd").unwrap();
+
+        let mut line = String::new();
+        rb.read_line(&mut line).unwrap();
+        assert_eq!(line, "ab\n");
+
+        // "cd" sits at the end of the storage, "ef\ng" wraps to the start
+        rb.write_all(b"ef\ng").unwrap();
+        assert_eq!(rb.as_slices(), (&b"cdef\n"[..], &b"g"[..]));
+
+        line.clear();
+        rb.read_line(&mut line).unwrap();
+        assert_eq!(line, "cdef\n");
+        line.clear();
+        rb.read_line(&mut line).unwrap();
+        assert_eq!(line, "g");
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_lines_across_wrap() {
+        let mut rb = HorridRing::with_capacity(5);
+        rb.write_all(b"xxx").unwrap();
+        rb.consume(3);
+        rb.write_all(b"a\nb\nc").unwrap();
+
+        let lines: Vec<_> = (&mut rb).lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_read_until_wrapped_delimiter() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.write_all(b"123").unwrap();
+        rb.consume(3);
+        // The delimiter is the first byte after the wrap
+        rb.write_all(b"ab;c").unwrap();
+
+        let mut buf = Vec::new();
+        assert_eq!(rb.read_until(b';', &mut buf).unwrap(), 3);
+        assert_eq!(buf, b"ab;");
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), b"c");
+    }
+
+    #[test]
+    fn test_consume_past_end() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.write_all(b"ab").unwrap();
+        rb.consume(10);
+        assert!(rb.fill_buf().unwrap().is_empty());
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_vectored() {
+        let mut rb = HorridRing::with_policy(6, FullPolicy::Reject);
+        let bufs = [IoSlice::new(b"abc"), IoSlice::new(b"de"), IoSlice::new(b"fgh")];
+        assert_eq!(rb.write_vectored(&bufs).unwrap(), 6);
+
+        let mut a = [0; 2];
+        let mut b = [0; 8];
+        let mut bufs = [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)];
+        assert_eq!(rb.read_vectored(&mut bufs).unwrap(), 6);
+        assert_eq!(&a, b"ab");
+        assert_eq!(&b[..4], b"cdef");
+    }
+
+    #[test]
+    fn test_fill_from_and_drain_to_file() {
+        let prefix = std::env::temp_dir().join("horrid-ring");
+        let path = crate::test_util::TempName::new(prefix.to_str().unwrap(), |path| {
+            let _ = std::fs::remove_file(path);
+        });
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        // A capacity that doesn't divide the file size, so reads wrap at different points
+        let mut rb = HorridRing::with_capacity(1000);
+        let mut src = std::fs::File::open(&path).unwrap();
+        let mut copy = Vec::new();
+        while rb.fill_from(&mut src).unwrap() > 0 {
+            rb.drain_to(&mut copy).unwrap();
+        }
+        rb.drain_to(&mut copy).unwrap();
+
+        assert_eq!(copy, data);
+    }
+
+    #[test]
+    fn test_fill_from_full_ring() {
+        let mut rb = HorridRing::with_capacity(2);
+        rb.write_all(b"ab").unwrap();
+        let err = rb.fill_from(&b"cd"[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), b"ab");
+
+        // Room again, but nothing left to read
+        assert_eq!(rb.fill_from(&b""[..]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_fill_from_inline_ring() {
+        let mut rb = InlineRing::<u8, 4>::new();
+        assert_eq!(rb.fill_from(&b"abc"[..]).unwrap(), 3);
+        assert_eq!(rb.pop(), Some(b'a'));
+
+        // Free space on both sides of the wrap point
+        assert_eq!(rb.fill_from(&b"defg"[..]).unwrap(), 2);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), b"bcde");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socketpair() {
+        use std::os::unix::net::UnixStream;
+
+        let (mut left, mut right) = UnixStream::pair().unwrap();
+        let mut rb = HorridRing::with_capacity(7);
+
+        for round in 0..50_u8 {
+            let msg = [round; 5];
+            left.write_all(&msg).unwrap();
+
+            let mut read = 0;
+            while read < msg.len() {
+                read += rb.fill_from(&mut right).unwrap();
+            }
+
+            // Five bytes a round through seven slots, so most rounds wrap
+            rb.drain_to(&mut left).unwrap();
+            let mut echoed = [0; 5];
+            right.read_exact(&mut echoed).unwrap();
+            assert_eq!(echoed, msg);
+        }
+    }
+
+    #[test]
+    fn test_grow_wrapped() {
+        let mut rb = HorridRing::with_capacity(4);
+        for i in 0..6 {
+            rb.push(i);
+        }
+        assert!(!rb.as_slices().1.is_empty());
+
+        rb.resize(6, Keep::Newest);
+        assert_eq!(rb.capacity(), 6);
+        assert_eq!(rb.as_slices(), (&[2, 3, 4, 5][..], &[][..]));
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (2, 6));
+
+        rb.push(6);
+        rb.push(7);
+        assert_eq!(rb.push(8), Some(2));
+    }
+
+    #[test]
+    fn test_shrink_keeps_newest() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(4);
+        for i in 0..6 {
+            rb.push((i, DropCounter(drops.clone())));
+        }
+        assert_eq!(drops.get(), 2);
+
+        rb.resize(2, Keep::Newest);
+        assert_eq!(drops.get(), 4);
+        assert_eq!(rb.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [4, 5]);
+        assert_eq!(rb.head_seq(), 4);
+
+        drop(rb);
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn test_shrink_keeps_oldest() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.push(0);
+        rb.pop();
+        for i in 1..5 {
+            rb.push(i);
+        }
+
+        // Whatever the policy says about pushing
+        assert_eq!(rb.policy(), FullPolicy::OverwriteOldest);
+        rb.resize(3, Keep::Oldest);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(rb.tail_seq(), 4);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut rb = HorridRing::with_capacity(2);
+        rb.push(1);
+        rb.reserve(1);
+        assert_eq!(rb.capacity(), 2);
+
+        rb.reserve(3);
+        assert_eq!(rb.capacity(), 4);
+        assert_eq!(rb.try_reserve(usize::MAX), Err(HorridError::CapacityOverflow));
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut rb = HorridRing::with_capacity(8);
+        rb.shrink_to_fit();
+        assert_eq!(rb.capacity(), 1);
+
+        rb.resize(8, Keep::Newest);
+        rb.push(1);
+        rb.push(2);
+        rb.shrink_to_fit();
+        assert_eq!(rb.capacity(), 2);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn test_resize_zero_leaves_ring() {
+        let mut rb = HorridRing::with_capacity(2);
+        rb.push(1);
+        assert_eq!(rb.try_resize(0, Keep::Newest), Err(HorridError::ZeroCapacity));
+        assert_eq!(rb.capacity(), 2);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn test_resize_zst() {
+        let mut rb = HorridRing::with_capacity(2);
+        rb.push(());
+        rb.push(());
+        rb.resize(1, Keep::Oldest);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [()]);
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut rb = HorridRing::with_capacity(3);
+        rb.extend([1, 2, 3, 4]);
+        assert_eq!(format!("{:?}", rb), "[2, 3, 4]");
+    }
+
+    #[test]
+    fn test_eq_ignores_layout() {
+        let mut wrapped = HorridRing::with_capacity(3);
+        wrapped.extend([0, 1, 2, 3]);
+        assert!(!wrapped.as_slices().1.is_empty());
+
+        let mut flat: InlineRing<i32, 8> = InlineRing::new();
+        flat.extend(&[1, 2, 3]);
+        assert_eq!(wrapped, flat);
+
+        flat.pop_back();
+        assert_ne!(wrapped, flat);
+    }
+
+    #[test]
+    fn test_hash_ignores_layout() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash(ring: &HorridRing<i32>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            ring.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut wrapped = HorridRing::with_capacity(3);
+        wrapped.extend([0, 1, 2, 3]);
+        let mut flat = HorridRing::with_capacity(5);
+        flat.extend([1, 2, 3]);
+        assert_eq!(hash(&wrapped), hash(&flat));
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut rb = HorridRing::with_policy(3, FullPolicy::Reject);
+        rb.extend(["a", "b", "c", "d"].iter().map(|s| s.to_string()));
+        rb.pop();
+        rb.push("d".to_string());
+
+        let copy = rb.clone();
+        assert_eq!(copy, rb);
+        assert_eq!(copy.capacity(), 3);
+        assert_eq!(copy.policy(), FullPolicy::Reject);
+        assert_eq!((copy.head_seq(), copy.tail_seq()), (1, 4));
+    }
+
+    #[test]
+    fn test_clone_inline() {
+        let mut rb: InlineRing<u8, 2> = InlineRing::new();
+        rb.extend([1, 2, 3]);
+        let copy = rb.clone();
+        assert_eq!(copy, rb);
+        assert_eq!(copy.get_by_seq(1), Some(&2));
+    }
+
+    #[test]
+    fn test_from_iter_keeps_last() {
+        let rb: HorridRing<usize> = (0..100).collect();
+        assert_eq!(rb.capacity(), HorridRing::<usize>::DEFAULT_CAPACITY);
+        assert!(rb.iter().copied().eq(100 - rb.capacity()..100));
+
+        let rb: InlineRing<usize, 3> = (0..10).collect();
+        assert!(rb.iter().copied().eq(7..10));
+    }
+
+    #[test]
+    fn test_len_accessors() {
+        let mut rb: HorridRing<u8> = HorridRing::default();
+        assert!(rb.is_empty());
+        assert_eq!(rb.capacity(), HorridRing::<u8>::DEFAULT_CAPACITY);
+
+        rb.extend(&[0; 16]);
+        assert!(rb.is_full());
+        assert_eq!(rb.len(), 16);
+    }
+
+    #[test]
+    fn test_get_across_wrap() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend(0..6);
+
+        assert_eq!(rb.get(0), Some(&2));
+        assert_eq!(rb.get(3), Some(&5));
+        assert_eq!(rb.get(4), None);
+        assert_eq!(rb.get_back(0), Some(&5));
+        assert_eq!(rb.get_back(2), Some(&3));
+        assert_eq!(rb.get_back(4), None);
+        assert_eq!(rb.get_back(usize::MAX), None);
+        assert_eq!(unsafe { *rb.get_unchecked(2) }, 4);
+
+        *rb.get_mut(1).unwrap() = 30;
+        *rb.get_back_mut(0).unwrap() = 50;
+        unsafe { *rb.get_unchecked_mut(0) = 20 };
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [20, 30, 4, 50]);
+    }
+
+    #[test]
+    fn test_index() {
+        let mut rb: InlineRing<i32, 3> = (0..5).collect();
+        assert_eq!(rb[0], 2);
+        rb[2] += 10;
+        assert_eq!(rb[2], 14);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of range for ring of length 2")]
+    fn test_index_out_of_range() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend([1, 2]);
+        let _ = rb[2];
+    }
+
+    #[test]
+    fn test_drain_range_matches_vec_deque() {
+        use std::collections::VecDeque;
+
+        // Every range, with the values starting at every slot
+        for shift in 0..5 {
+            for len in 0..=5 {
+                for start in 0..=len {
+                    for end in start..=len {
+                        let mut rb = HorridRing::with_capacity(5);
+                        for _ in 0..shift {
+                            rb.push(0);
+                            rb.pop();
+                        }
+                        rb.extend(0..len);
+                        let mut model: VecDeque<_> = (0..len).collect();
+
+                        let drained: Vec<_> = rb.drain(start..end).collect();
+                        let expected: Vec<_> = model.drain(start..end).collect();
+                        assert_eq!(drained, expected);
+                        assert!(rb.iter().eq(model.iter()));
+                        assert_eq!(rb.len() as u64, rb.tail_seq() - rb.head_seq());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_drain_moves_shorter_side() {
+        // One value before the range, three after: the front moves
+        let mut rb = HorridRing::with_capacity(8);
+        rb.extend(0..6);
+        rb.drain(1..3);
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (2, 6));
+        assert_eq!(rb.get_by_seq(2), Some(&0));
+        assert_eq!(rb.get_by_seq(5), Some(&5));
+
+        // Three values before the range, one after: the back moves
+        let mut rb = HorridRing::with_capacity(8);
+        rb.extend(0..6);
+        rb.drain(3..5);
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (0, 4));
+        assert_eq!(rb.get_by_seq(3), Some(&5));
+    }
+
+    #[test]
+    fn test_drain_drops_rest() {
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = HorridRing::with_capacity(4);
+        for _ in 0..6 {
+            rb.push(DropCounter(drops.clone()));
+        }
+        assert_eq!(drops.get(), 2);
+
+        let mut drain = rb.drain(1..);
+        drop(drain.next_back());
+        assert_eq!(drops.get(), 3);
+        drop(drain);
+        assert_eq!(drops.get(), 5);
+        assert_eq!(rb.len(), 1);
+    }
+
+    #[test]
+    fn test_drain_drop_panics() {
+        struct PanicOnDrop(u32);
+
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                if self.0 == 1 {
+                    panic!("dropping {}", self.0);
+                }
+            }
+        }
+
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend((0..4).map(PanicOnDrop));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            drop(rb.drain(1..2));
+        }));
+        assert!(result.is_err());
+
+        // The values after the range are still there
+        assert_eq!(rb.iter().map(|v| v.0).collect::<Vec<_>>(), [0, 2, 3]);
+    }
+
+    #[test]
+    fn test_drain_leaked() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend(["a", "b", "c", "d"].iter().map(|s| s.to_string()));
+        std::mem::forget(rb.drain(1..3));
+
+        // The values from the start of the range on are leaked
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend(0..6);
+
+        assert_eq!(rb.range(1..3).copied().collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(rb.range(..=1).rev().copied().collect::<Vec<_>>(), [3, 2]);
+        assert_eq!(rb.range(2..).len(), 2);
+
+        rb.range_mut(1..).for_each(|v| *v *= 10);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [2, 30, 40, 50]);
+    }
+
+    #[test]
+    #[should_panic(expected = "range end 5 out of range for ring of length 4")]
+    fn test_range_out_of_bounds() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend(0..4);
+        rb.range(2..5);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend(0..6);
+
+        rb.truncate_front(3);
+        assert_eq!(rb.head_seq(), 3);
+        rb.truncate_back(2);
+        assert_eq!(rb.tail_seq(), 5);
+        rb.truncate_back(5);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [3, 4]);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut rb = HorridRing::with_policy(4, FullPolicy::Reject);
+        rb.push(0);
+        rb.pop();
+        rb.extend(1..5);
+
+        let back = rb.split_off(1);
+        assert_eq!(back.capacity(), 4);
+        assert_eq!(back.policy(), FullPolicy::Reject);
+        assert_eq!((back.head_seq(), back.tail_seq()), (2, 5));
+        assert!(back.iter().copied().eq(2..5));
+
+        assert_eq!((rb.head_seq(), rb.tail_seq()), (1, 2));
+        assert!(rb.iter().copied().eq(1..2));
+    }
+
+    #[test]
+    fn test_append_overwrite() {
+        let mut rb = HorridRing::with_capacity(4);
+        rb.extend(0..3);
+        let mut other: InlineRing<i32, 4> = (10..13).collect();
+
+        rb.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [2, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_append_reject() {
+        let mut rb = HorridRing::with_policy(4, FullPolicy::Reject);
+        rb.extend(0..3);
+        let mut other = HorridRing::with_capacity(4);
+        other.extend(10..13);
+
+        rb.append(&mut other);
+        assert_eq!(rb.drain(..).collect::<Vec<_>>(), [0, 1, 2, 10]);
+        assert_eq!(other.drain(..).collect::<Vec<_>>(), [11, 12]);
+    }
 }
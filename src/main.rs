@@ -1,6 +1,6 @@
 use horrid_ring_buffer::HorridRing;
 
 fn main() {
-    let ring_buffer = HorridRing::<u8>::with_capacity(8);
+    let _ring_buffer = HorridRing::<u8>::with_capacity(8);
 
 }
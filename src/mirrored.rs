@@ -0,0 +1,230 @@
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+use crate::storage::sealed::Sealed;
+use crate::{FullPolicy, HorridError, HorridRing, Storage};
+
+// -----------------------------------------------------------------------------
+//     - Mirrored storage -
+// -----------------------------------------------------------------------------
+/// Byte storage mapped twice, back to back, in virtual memory.
+///
+/// Byte `capacity + i` is the same memory as byte `i`, so any run of
+/// bytes in the ring can be read as one slice, even across the wrap point.
+///
+/// Both mappings have to be whole pages, so the capacity is rounded up
+/// to a multiple of the page size.
+pub struct Mirrored {
+    inner: NonNull<u8>,
+    capacity: usize,
+}
+
+impl Mirrored {
+    pub(crate) fn try_with_capacity(capacity: usize) -> Result<Self, HorridError> {
+        if capacity == 0 {
+            return Err(HorridError::ZeroCapacity);
+        }
+
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let capacity = capacity
+            .checked_next_multiple_of(page)
+            .filter(|cap| *cap <= isize::MAX as usize / 2)
+            .ok_or(HorridError::CapacityOverflow)?;
+
+        unsafe {
+            let fd = libc::memfd_create(b"horrid-ring\0".as_ptr().cast(), libc::MFD_CLOEXEC);
+            if fd == -1 {
+                return Err(HorridError::last_os_error());
+            }
+
+            let mapped = map_twice(fd, capacity);
+            // The mappings keep the memory alive on their own
+            libc::close(fd);
+
+            let inner = mapped?;
+            Ok(Self { inner, capacity })
+        }
+    }
+}
+
+// Map the file behind `fd` twice into one reserved run of address space.
+unsafe fn map_twice(fd: libc::c_int, capacity: usize) -> Result<NonNull<u8>, HorridError> {
+    // Growing the file fills it with zeroes
+    if libc::ftruncate(fd, capacity as libc::off_t) == -1 {
+        return Err(HorridError::last_os_error());
+    }
+
+    // Reserve room for both mappings first, so nothing else can end up
+    // between them.
+    let base = libc::mmap(
+        ptr::null_mut(),
+        capacity * 2,
+        libc::PROT_NONE,
+        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+        -1,
+        0,
+    );
+    if base == libc::MAP_FAILED {
+        return Err(HorridError::last_os_error());
+    }
+
+    for half in [base, base.cast::<u8>().add(capacity).cast()] {
+        let mapped = libc::mmap(
+            half,
+            capacity,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_FIXED,
+            fd,
+            0,
+        );
+
+        if mapped == libc::MAP_FAILED {
+            let err = HorridError::last_os_error();
+            libc::munmap(base, capacity * 2);
+            return Err(err);
+        }
+    }
+
+    Ok(NonNull::new_unchecked(base.cast()))
+}
+
+impl Sealed for Mirrored {}
+
+unsafe impl Storage<u8> for Mirrored {
+    fn as_ptr(&self) -> *const u8 {
+        self.inner.as_ptr()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.inner.as_ptr()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+unsafe impl Send for Mirrored {}
+unsafe impl Sync for Mirrored {}
+
+impl Drop for Mirrored {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.inner.as_ptr().cast(), self.capacity * 2) };
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Mirrored ring -
+// -----------------------------------------------------------------------------
+/// A byte ring on [`Mirrored`] storage.
+///
+/// Besides the usual two slice views, [`as_slice`](Self::as_slice)
+/// returns every unread byte as one contiguous slice.
+pub type MirroredRing = HorridRing<u8, Mirrored>;
+
+impl MirroredRing {
+    /// The capacity is rounded up to a multiple of the page size.
+    /// [`capacity`](Self::capacity) returns the capacity after rounding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`try_new`](Self::try_new) would fail.
+    pub fn new(capacity: usize) -> Self {
+        match Self::try_new(capacity) {
+            Ok(ring) => ring,
+            Err(e) => panic!("could not create ring: {}", e),
+        }
+    }
+
+    /// The capacity is rounded up to a multiple of the page size.
+    ///
+    /// Fails if `capacity` is zero, if twice the rounded capacity would not
+    /// fit in the address space, or with [`HorridError::Os`] if the memory
+    /// could not be mapped.
+    pub fn try_new(capacity: usize) -> Result<Self, HorridError> {
+        let ring = Self {
+            read: 0,
+            head: 0,
+            tail: 0,
+            storage: Mirrored::try_with_capacity(capacity)?,
+            policy: FullPolicy::default(),
+            _marker: PhantomData,
+        };
+
+        Ok(ring)
+    }
+
+    /// Every unread byte, oldest to newest, as one slice.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.storage.as_ptr().add(self.read), self.len()) }
+    }
+
+    /// Mutable version of [`as_slice`](Self::as_slice).
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        let len = self.len();
+        unsafe { slice::from_raw_parts_mut(self.storage.as_mut_ptr().add(self.read), len) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+
+    #[test]
+    fn test_capacity_rounds_to_page() {
+        let page = page_size();
+        assert_eq!(MirroredRing::new(1).capacity(), page);
+        assert_eq!(MirroredRing::new(page).capacity(), page);
+        assert_eq!(MirroredRing::new(page + 1).capacity(), page * 2);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        assert_eq!(MirroredRing::try_new(0).err(), Some(HorridError::ZeroCapacity));
+    }
+
+    #[test]
+    fn test_as_slice_across_wrap() {
+        let mut rb = MirroredRing::new(1);
+        let cap = rb.capacity();
+
+        rb.write_all(&vec![0; cap - 3]).unwrap();
+        rb.read_exact(&mut vec![0; cap - 3]).unwrap();
+        rb.write_all(b"hello world").unwrap();
+
+        // The two slice views split at the wrap point, `as_slice` does not
+        assert_eq!(rb.as_slices(), (&b"hel"[..], &b"lo world"[..]));
+        assert_eq!(rb.as_slice(), b"hello world");
+
+        rb.as_mut_slice().make_ascii_uppercase();
+        let mut buf = [0; 11];
+        rb.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"HELLO WORLD");
+    }
+
+    #[test]
+    fn test_reject_policy() {
+        let mut rb = MirroredRing::new(1);
+        rb.set_policy(FullPolicy::Reject);
+        let cap = rb.capacity();
+        assert_eq!(rb.write(&vec![1; cap + 1]).unwrap(), cap);
+        assert_eq!(rb.as_slice().len(), cap);
+    }
+
+    #[test]
+    fn test_overwrite_full_ring() {
+        let mut rb = MirroredRing::new(1);
+        let cap = rb.capacity();
+        let data: Vec<u8> = (0..cap + 100).map(|i| i as u8).collect();
+        rb.write_all(&data).unwrap();
+
+        assert_eq!(rb.as_slice(), &data[100..]);
+        assert_eq!(rb.head_seq(), 100);
+    }
+}
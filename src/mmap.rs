@@ -0,0 +1,21 @@
+use core::ptr::{self, NonNull};
+
+use crate::HorridError;
+
+// Map `len` bytes of the file behind `fd`, shared with everyone else who maps it.
+pub(crate) unsafe fn map(fd: libc::c_int, len: usize) -> Result<NonNull<u8>, HorridError> {
+    let mapped = libc::mmap(
+        ptr::null_mut(),
+        len,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_SHARED,
+        fd,
+        0,
+    );
+
+    if mapped == libc::MAP_FAILED {
+        return Err(HorridError::last_os_error());
+    }
+
+    Ok(NonNull::new_unchecked(mapped.cast()))
+}
@@ -0,0 +1,607 @@
+//! Bounded multi producer, multi consumer queue on [`HorridRing`](crate::HorridRing) storage.
+//!
+//! Every slot carries a stamp saying whose turn it is: a producer may write
+//! a slot when the stamp equals its position, and a consumer may read it when
+//! the stamp is one past its position. This is Dmitry Vyukov's bounded queue.
+//!
+//! ```
+//! use horrid_ring_buffer::mpmc::HorridMpmc;
+//!
+//! let (tx, rx) = HorridMpmc::with_capacity(4).split();
+//! let tx2 = tx.clone();
+//! tx.try_send(1).unwrap();
+//! tx2.try_send(2).unwrap();
+//! assert_eq!(rx.clone().try_recv(), Some(1));
+//! assert_eq!(rx.try_recv(), Some(2));
+//! ```
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use crate::atomic::{backoff, fence, Arc, AtomicBool, AtomicUsize, CachePadded, Ordering};
+use crate::{FullPolicy, Heap, HorridError, Storage};
+
+// -----------------------------------------------------------------------------
+//     - Slot -
+// -----------------------------------------------------------------------------
+struct Slot<T> {
+    stamp: AtomicUsize,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+// -----------------------------------------------------------------------------
+//     - Queue -
+// -----------------------------------------------------------------------------
+/// A fixed capacity queue shared by any number of producers and consumers.
+///
+/// Positions are stored as a lap count in the high bits and a slot index in
+/// the low bits, so a position is never mistaken for one a lap earlier.
+pub struct HorridMpmc<T> {
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    slots: Heap<Slot<T>>,
+    // Smallest power of two above the capacity, the position step of one lap
+    one_lap: usize,
+    policy: FullPolicy,
+    // Live handles on each side, only counted once the queue is split
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+    // Set when the last sender or the last receiver is dropped
+    disconnected: AtomicBool,
+}
+
+impl<T> HorridMpmc<T> {
+    /// # Panics
+    ///
+    /// Panics if [`try_with_policy`](Self::try_with_policy) would fail.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_policy(capacity, FullPolicy::default())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if [`try_with_policy`](Self::try_with_policy) would fail.
+    pub fn with_policy(capacity: usize, policy: FullPolicy) -> Self {
+        match Self::try_with_policy(capacity, policy) {
+            Ok(queue) => queue,
+            Err(e) => panic!("could not create queue: {}", e),
+        }
+    }
+
+    /// Fails for the same reasons as [`HorridRing::try_with_policy`](crate::HorridRing::try_with_policy),
+    /// or if the lap counter would not fit next to the slot index.
+    pub fn try_with_policy(capacity: usize, policy: FullPolicy) -> Result<Self, HorridError> {
+        let one_lap = capacity
+            .checked_add(1)
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or(HorridError::CapacityOverflow)?;
+
+        let mut slots = Heap::<Slot<T>>::try_with_capacity(capacity)?;
+        for i in 0..capacity {
+            let slot = Slot {
+                stamp: AtomicUsize::new(i),
+                val: UnsafeCell::new(MaybeUninit::uninit()),
+            };
+            unsafe { ptr::write(slots.as_mut_ptr().add(i), slot) };
+        }
+
+        let queue = Self {
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+            slots,
+            one_lap,
+            policy,
+            senders: AtomicUsize::new(0),
+            receivers: AtomicUsize::new(0),
+            disconnected: AtomicBool::new(false),
+        };
+
+        Ok(queue)
+    }
+
+    /// Split into a sender and a receiver, which can both be cloned.
+    pub fn split(mut self) -> (Sender<T>, Receiver<T>) {
+        self.senders = AtomicUsize::new(1);
+        self.receivers = AtomicUsize::new(1);
+        let queue = Arc::new(self);
+        let sender = Sender { queue: queue.clone() };
+        (sender, Receiver { queue })
+    }
+
+    pub fn policy(&self) -> FullPolicy {
+        self.policy
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Number of values in the queue. Other threads may be sending and
+    /// receiving concurrently, so this can be out of date as soon as it returns.
+    pub fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.load(Ordering::SeqCst);
+            let head = self.head.load(Ordering::SeqCst);
+
+            // Only trust the pair if the tail didn't move while reading the head
+            if self.tail.load(Ordering::SeqCst) == tail {
+                return self.distance(head, tail);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Send a value, applying the [`FullPolicy`] if the queue is full.
+    ///
+    /// With `OverwriteOldest` this returns the evicted value, if any.
+    /// With `Reject` this returns `val` if there was no room for it.
+    /// With `Block` this waits for a receiver to make room.
+    ///
+    /// Whatever the policy, `val` is handed back once every [`Receiver`]
+    /// has been dropped.
+    pub fn send(&self, mut val: T) -> Option<T> {
+        match self.policy {
+            FullPolicy::OverwriteOldest => self.force_send(val),
+            FullPolicy::Reject => self.try_send(val).err(),
+            FullPolicy::Block => loop {
+                if self.is_disconnected() {
+                    return Some(val);
+                }
+
+                match self.try_send(val) {
+                    Ok(()) => return None,
+                    Err(v) => val = v,
+                }
+                backoff();
+            },
+        }
+    }
+
+    /// Send a value if there is room for it, regardless of policy.
+    ///
+    /// Hands `val` back if every [`Receiver`] has been dropped.
+    pub fn try_send(&self, val: T) -> Result<(), T> {
+        if self.is_disconnected() {
+            return Err(val);
+        }
+
+        self.send_or_else(val, |val, tail, _, _| {
+            let head = self.head.load(Ordering::Relaxed);
+            if head.wrapping_add(self.one_lap) == tail {
+                Err(val)
+            } else {
+                Ok(val)
+            }
+        })
+    }
+
+    /// Send a value, evicting the oldest value if the queue is full.
+    ///
+    /// Hands `val` back if every [`Receiver`] has been dropped.
+    pub fn force_send(&self, val: T) -> Option<T> {
+        if self.is_disconnected() {
+            return Some(val);
+        }
+
+        let sent = self.send_or_else(val, |val, tail, new_tail, slot| {
+            let head = tail.wrapping_sub(self.one_lap);
+            let new_head = new_tail.wrapping_sub(self.one_lap);
+
+            // Taking the head first keeps receivers away from the slot
+            if self
+                .head
+                .compare_exchange_weak(head, new_head, Ordering::SeqCst, Ordering::Relaxed)
+                .is_err()
+            {
+                return Ok(val);
+            }
+
+            self.tail.store(new_tail, Ordering::SeqCst);
+            let old = unsafe { slot.val.get().replace(MaybeUninit::new(val)).assume_init() };
+            slot.stamp.store(tail + 1, Ordering::Release);
+            Err(old)
+        });
+
+        sent.err()
+    }
+
+    /// Receive the oldest value, if any.
+    pub fn try_recv(&self) -> Option<T> {
+        let mut head = self.head.load(Ordering::Relaxed);
+
+        loop {
+            let (index, lap) = self.split_pos(head);
+            let slot = self.slot(index);
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if head + 1 == stamp {
+                // The slot has been written, try to claim it
+                let new_head = self.next_pos(index, lap, head);
+                match self.head.compare_exchange_weak(head, new_head, Ordering::SeqCst, Ordering::Relaxed) {
+                    Ok(_) => {
+                        let val = unsafe { slot.val.get().read().assume_init() };
+                        slot.stamp.store(head.wrapping_add(self.one_lap), Ordering::Release);
+                        return Some(val);
+                    }
+                    Err(h) => head = h,
+                }
+            } else if stamp == head {
+                // The slot is waiting for a value. Empty unless a send is in progress.
+                fence(Ordering::SeqCst);
+                if self.tail.load(Ordering::Relaxed) == head {
+                    return None;
+                }
+
+                backoff();
+                head = self.head.load(Ordering::Relaxed);
+            } else {
+                // Another receiver got here first
+                backoff();
+                head = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    // `full` is called when the slot at `tail` still holds last lap's value.
+    // It returns `Ok(val)` to try again, or `Err` to give up with a value.
+    fn send_or_else<F>(&self, mut val: T, full: F) -> Result<(), T>
+    where
+        F: Fn(T, usize, usize, &Slot<T>) -> Result<T, T>,
+    {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+
+        loop {
+            let (index, lap) = self.split_pos(tail);
+            let new_tail = self.next_pos(index, lap, tail);
+            let slot = self.slot(index);
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            if tail == stamp {
+                // The slot is free, try to claim it
+                match self.tail.compare_exchange_weak(tail, new_tail, Ordering::SeqCst, Ordering::Relaxed) {
+                    Ok(_) => {
+                        unsafe { slot.val.get().write(MaybeUninit::new(val)) };
+                        slot.stamp.store(tail + 1, Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(t) => tail = t,
+                }
+            } else if stamp.wrapping_add(self.one_lap) == tail + 1 {
+                fence(Ordering::SeqCst);
+                val = full(val, tail, new_tail, slot)?;
+                backoff();
+                tail = self.tail.load(Ordering::Relaxed);
+            } else {
+                // Another sender got here first
+                backoff();
+                tail = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    // Whether every sender or every receiver of a split queue is gone
+    fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Acquire)
+    }
+
+    // Called when a handle is dropped, with the count for its side
+    fn release(&self, count: &AtomicUsize) {
+        if count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.disconnected.store(true, Ordering::Release);
+        }
+    }
+
+    fn split_pos(&self, pos: usize) -> (usize, usize) {
+        (pos & (self.one_lap - 1), pos & !(self.one_lap - 1))
+    }
+
+    // The position after `pos`, moving to the next lap past the last slot
+    fn next_pos(&self, index: usize, lap: usize, pos: usize) -> usize {
+        if index + 1 < self.capacity() {
+            pos + 1
+        } else {
+            lap.wrapping_add(self.one_lap)
+        }
+    }
+
+    fn distance(&self, head: usize, tail: usize) -> usize {
+        let (head_index, _) = self.split_pos(head);
+        let (tail_index, _) = self.split_pos(tail);
+
+        if head_index < tail_index {
+            tail_index - head_index
+        } else if head_index > tail_index {
+            self.capacity() - head_index + tail_index
+        } else if tail == head {
+            0
+        } else {
+            self.capacity()
+        }
+    }
+
+    fn slot(&self, index: usize) -> &Slot<T> {
+        unsafe { &*self.slots.as_ptr().add(index) }
+    }
+}
+
+impl<T> Drop for HorridMpmc<T> {
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Relaxed);
+        let (head_index, _) = self.split_pos(head);
+
+        for i in 0..self.distance(head, tail) {
+            let index = (head_index + i) % self.capacity();
+            unsafe { (*self.slot(index).val.get()).assume_init_drop() };
+        }
+
+        // The heap storage only frees the memory, so drop the slots themselves
+        for index in 0..self.capacity() {
+            unsafe { ptr::drop_in_place(self.slots.as_mut_ptr().add(index)) };
+        }
+    }
+}
+
+// Every value is handed from exactly one sender to exactly one receiver,
+// with the slot stamps making sure they take turns.
+unsafe impl<T: Send> Send for HorridMpmc<T> {}
+unsafe impl<T: Send> Sync for HorridMpmc<T> {}
+
+// -----------------------------------------------------------------------------
+//     - Sender -
+// -----------------------------------------------------------------------------
+/// A cloneable sending handle to a [`HorridMpmc`].
+pub struct Sender<T> {
+    queue: Arc<HorridMpmc<T>>,
+}
+
+impl<T> Sender<T> {
+    /// See [`HorridMpmc::send`].
+    pub fn send(&self, val: T) -> Option<T> {
+        self.queue.send(val)
+    }
+
+    /// See [`HorridMpmc::try_send`].
+    pub fn try_send(&self, val: T) -> Result<(), T> {
+        self.queue.try_send(val)
+    }
+
+    /// See [`HorridMpmc::force_send`].
+    pub fn force_send(&self, val: T) -> Option<T> {
+        self.queue.force_send(val)
+    }
+
+    /// Whether every receiver has been dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.queue.is_disconnected()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.queue.senders.fetch_add(1, Ordering::Relaxed);
+        Self { queue: self.queue.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.queue.release(&self.queue.senders);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Receiver -
+// -----------------------------------------------------------------------------
+/// A cloneable receiving handle to a [`HorridMpmc`].
+pub struct Receiver<T> {
+    queue: Arc<HorridMpmc<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// See [`HorridMpmc::try_recv`].
+    pub fn try_recv(&self) -> Option<T> {
+        self.queue.try_recv()
+    }
+
+    /// Whether every sender has been dropped. Values sent before
+    /// that can still be received.
+    pub fn is_disconnected(&self) -> bool {
+        self.queue.is_disconnected()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.queue.capacity()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.queue.receivers.fetch_add(1, Ordering::Relaxed);
+        Self { queue: self.queue.clone() }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.queue.release(&self.queue.receivers);
+    }
+}
+
+#[cfg(all(test, not(loom), feature = "std"))]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+    use std::thread;
+
+    #[test]
+    fn test_send_recv() {
+        let (tx, rx) = HorridMpmc::with_policy(3, FullPolicy::Reject).split();
+        assert_eq!(tx.try_send(1), Ok(()));
+        assert_eq!(tx.try_send(2), Ok(()));
+        assert_eq!(tx.try_send(3), Ok(()));
+        assert_eq!(tx.send(4), Some(4));
+        assert_eq!(tx.try_send(4), Err(4));
+        assert_eq!(rx.len(), 3);
+
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(tx.try_send(4), Ok(()));
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.try_recv(), Some(3));
+        assert_eq!(rx.try_recv(), Some(4));
+        assert_eq!(rx.try_recv(), None);
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let (tx, rx) = HorridMpmc::with_capacity(2).split();
+        assert_eq!(tx.send(1), None);
+        assert_eq!(tx.send(2), None);
+        assert_eq!(tx.send(3), Some(1));
+        assert_eq!(tx.send(4), Some(2));
+        assert_eq!(rx.try_recv(), Some(3));
+        assert_eq!(rx.try_recv(), Some(4));
+        assert_eq!(rx.try_recv(), None);
+    }
+
+    #[test]
+    fn test_drops_remaining() {
+        let val = std::rc::Rc::new(());
+        {
+            let queue = HorridMpmc::with_capacity(3);
+            for _ in 0..5 {
+                drop(queue.send(val.clone()));
+            }
+            assert_eq!(std::rc::Rc::strong_count(&val), 4);
+        }
+        assert_eq!(std::rc::Rc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn test_send_after_receivers_dropped() {
+        let (tx, rx) = HorridMpmc::with_capacity(2).split();
+        let rx2 = rx.clone();
+        drop(rx);
+        assert!(!tx.is_disconnected());
+        assert_eq!(tx.try_send(1), Ok(()));
+
+        drop(rx2);
+        assert!(tx.is_disconnected());
+        assert_eq!(tx.try_send(2), Err(2));
+        assert_eq!(tx.send(3), Some(3));
+        assert_eq!(tx.force_send(4), Some(4));
+    }
+
+    #[test]
+    fn test_blocked_send_wakes_when_receivers_dropped() {
+        let (tx, rx) = HorridMpmc::with_policy(1, FullPolicy::Block).split();
+        let rx2 = rx.clone();
+        assert_eq!(tx.send(1), None);
+
+        // The queue is full, so this waits until nothing can ever make room
+        let handle = thread::spawn(move || tx.send(2));
+        drop(rx);
+        drop(rx2);
+        assert_eq!(handle.join().unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_recv_after_senders_dropped() {
+        let (tx, rx) = HorridMpmc::with_capacity(2).split();
+        tx.clone().try_send(1).unwrap();
+        assert!(!rx.is_disconnected());
+
+        drop(tx);
+        assert!(rx.is_disconnected());
+        assert_eq!(rx.try_recv(), Some(1));
+        assert_eq!(rx.try_recv(), None);
+    }
+
+    const PRODUCERS: usize = 4;
+    const CONSUMERS: usize = 4;
+    const PER_PRODUCER: usize = 5_000;
+
+    // Runs producers and consumers against the queue, returning everything
+    // that was received or evicted.
+    fn stress(queue: HorridMpmc<usize>) -> Vec<usize> {
+        let (tx, rx) = queue.split();
+        let seen = Mutex::new(Vec::new());
+
+        thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let tx = tx.clone();
+                let seen = &seen;
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        if let Some(evicted) = tx.send(p * PER_PRODUCER + i) {
+                            seen.lock().unwrap().push(evicted);
+                        }
+                    }
+                });
+            }
+
+            for _ in 0..CONSUMERS {
+                let rx = rx.clone();
+                let seen = &seen;
+                s.spawn(move || loop {
+                    match rx.try_recv() {
+                        Some(usize::MAX) => break,
+                        Some(val) => seen.lock().unwrap().push(val),
+                        None => thread::yield_now(),
+                    }
+                });
+            }
+
+            // Wait for the producers, then tell each consumer to stop.
+            // Stopping values are never evicted: nothing else is sent after them.
+            while seen.lock().unwrap().len() + rx.len() < PRODUCERS * PER_PRODUCER {
+                thread::yield_now();
+            }
+            for _ in 0..CONSUMERS {
+                while tx.try_send(usize::MAX).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort_unstable();
+        seen
+    }
+
+    #[test]
+    fn test_stress_block() {
+        let seen = stress(HorridMpmc::with_policy(8, FullPolicy::Block));
+        assert_eq!(seen, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_stress_overwrite() {
+        let seen = stress(HorridMpmc::with_policy(3, FullPolicy::OverwriteOldest));
+        assert_eq!(seen, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+    }
+}
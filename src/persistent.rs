@@ -0,0 +1,721 @@
+//! A ring of fixed size byte records in a memory mapped file, which
+//! survives the process crashing.
+//!
+//! Every record is stored with its sequence number and a checksum. There
+//! is no head or tail on disk: [`PersistentRing::open`] finds them by
+//! looking for the newest run of records with valid checksums. A record
+//! that was half written when the process died fails its checksum and is
+//! skipped, along with anything older than it.
+//!
+//! Records are in the page cache as soon as they are pushed or popped, so
+//! a process crash loses nothing. Call [`flush`](PersistentRing::flush) to
+//! also survive the machine going down.
+//!
+//! A ring holds an exclusive `flock` on its file for as long as it is
+//! open, so the records it hands out can't change underneath it. Opening
+//! the same file again, from this process or another, fails until the
+//! first ring is dropped. The lock is advisory: it keeps out other rings,
+//! not programs that write to the file without asking for it.
+//!
+//! ```no_run
+//! use horrid_ring_buffer::persistent::PersistentRing;
+//!
+//! let mut ring = PersistentRing::<32>::create("flight.log", 1024).unwrap();
+//! ring.push([0; 32]);
+//! drop(ring);
+//!
+//! let ring = PersistentRing::<32>::open("flight.log").unwrap();
+//! assert_eq!(ring.len(), 1);
+//! ```
+use core::convert::TryInto;
+use core::ptr::NonNull;
+use core::slice;
+use core::sync::atomic::{fence, Ordering};
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use crate::mmap::map;
+use crate::{FullPolicy, HorridError};
+
+const MAGIC: [u8; 8] = *b"HORRIDPR";
+const VERSION: u32 = 1;
+
+// -----------------------------------------------------------------------------
+//     - File layout -
+// -----------------------------------------------------------------------------
+// All numbers are little endian.
+//
+// Header, padded to `HEADER_LEN`:
+//     magic:       [u8; 8]
+//     version:     u32
+//     record_size: u32
+//     capacity:    u64
+//     checksum:    u32, of the fields above
+//
+// Followed by `capacity` slots of `SLOT_META + record_size` bytes:
+//     seq:         u64
+//     checksum:    u32, of `seq` and the record
+//     flags:       u32, `POPPED` once the record has been popped
+//     record:      [u8; record_size]
+const HEADER_LEN: usize = 64;
+const HEADER_FIELDS: usize = 24;
+const SLOT_META: usize = 16;
+const FLAGS: usize = 12;
+const POPPED: u8 = 1;
+
+fn file_len(capacity: usize, record_size: usize) -> Option<usize> {
+    (SLOT_META + record_size)
+        .checked_mul(capacity)?
+        .checked_add(HEADER_LEN)
+        .filter(|len| *len <= isize::MAX as usize)
+}
+
+// -----------------------------------------------------------------------------
+//     - Persistent ring -
+// -----------------------------------------------------------------------------
+/// A fixed capacity ring of `N` byte records, kept in a memory mapped file.
+///
+/// Pushing and popping work like they do on a [`HorridRing`](crate::HorridRing),
+/// including the [`FullPolicy`]. The policy is not stored in the file, and
+/// a ring that was just opened overwrites the oldest record like any other.
+pub struct PersistentRing<const N: usize> {
+    inner: NonNull<u8>,
+    // Holds the lock, closing it lets another ring open the file
+    _file: File,
+    map_len: usize,
+    capacity: usize,
+    head: u64,
+    tail: u64,
+    policy: FullPolicy,
+}
+
+impl<const N: usize> PersistentRing<N> {
+    /// Create an empty ring, replacing whatever is at `path`.
+    ///
+    /// Fails with [`HorridError::Os`]`(EWOULDBLOCK)`, leaving the file as
+    /// it was, if another ring has it open.
+    pub fn create(path: impl AsRef<Path>, capacity: usize) -> Result<Self, HorridError> {
+        if capacity == 0 {
+            return Err(HorridError::ZeroCapacity);
+        }
+
+        let map_len = file_len(capacity, N)
+            .filter(|_| N <= u32::MAX as usize)
+            .ok_or(HorridError::CapacityOverflow)?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(io_error)?;
+
+        // Only throw the old records away once no other ring is using them
+        lock(&file)?;
+        file.set_len(0).map_err(io_error)?;
+
+        // Extends the file with zeros, which never pass as a valid record
+        file.set_len(map_len as u64).map_err(io_error)?;
+
+        let mut ring = Self {
+            inner: unsafe { map(file.as_raw_fd(), map_len)? },
+            _file: file,
+            map_len,
+            capacity,
+            head: 0,
+            tail: 0,
+            policy: FullPolicy::default(),
+        };
+
+        ring.write_header();
+        Ok(ring)
+    }
+
+    /// Open a ring created by [`create`](Self::create), recovering the
+    /// records that were fully written and not popped.
+    ///
+    /// A file that was cut short keeps the records that are still in it,
+    /// and is extended back to its full length.
+    ///
+    /// Fails with [`HorridError::InvalidHeader`] if the file does not start
+    /// with a valid header or holds records of a size other than `N`, and
+    /// with [`HorridError::Os`]`(EWOULDBLOCK)` if another ring has it open.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, HorridError> {
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(io_error)?;
+        lock(&file)?;
+        let (capacity, record_size) = read_header(&file)?;
+        if record_size != N {
+            return Err(HorridError::InvalidHeader);
+        }
+        let map_len = file_len(capacity, N).ok_or(HorridError::InvalidHeader)?;
+
+        if file.metadata().map_err(io_error)?.len() < map_len as u64 {
+            file.set_len(map_len as u64).map_err(io_error)?;
+        }
+
+        let mut ring = Self {
+            inner: unsafe { map(file.as_raw_fd(), map_len)? },
+            _file: file,
+            map_len,
+            capacity,
+            head: 0,
+            tail: 0,
+            policy: FullPolicy::default(),
+        };
+
+        ring.recover();
+        Ok(ring)
+    }
+
+    pub fn policy(&self) -> FullPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: FullPolicy) {
+        self.policy = policy;
+    }
+
+    /// Push a record, applying the [`FullPolicy`] if the ring is full.
+    ///
+    /// With `OverwriteOldest` this returns the evicted record, if any.
+    /// With `Reject` or `Block` this returns `record` if there was no room for it.
+    pub fn push(&mut self, record: [u8; N]) -> Option<[u8; N]> {
+        match self.policy {
+            FullPolicy::OverwriteOldest => {
+                let evicted = if self.is_full() { self.pop() } else { None };
+                self.write_back(&record);
+                evicted
+            }
+            FullPolicy::Reject | FullPolicy::Block => self.try_push(record).err(),
+        }
+    }
+
+    /// Push a record if there is room for it, regardless of policy.
+    pub fn try_push(&mut self, record: [u8; N]) -> Result<(), [u8; N]> {
+        if self.is_full() {
+            return Err(record);
+        }
+
+        self.write_back(&record);
+        Ok(())
+    }
+
+    /// Remove and return the oldest record.
+    ///
+    /// The record is marked as popped in the file, so it stays gone
+    /// when the ring is opened again.
+    pub fn pop(&mut self) -> Option<[u8; N]> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let seq = self.head;
+        let record = *self.record(seq);
+        // A single byte, so it can't be torn
+        self.slot_mut(seq)[FLAGS] = POPPED;
+        self.head = self.head.wrapping_add(1);
+        Some(record)
+    }
+
+    /// Write the records to disk, so they survive the machine going down
+    /// and not just the process.
+    pub fn flush(&self) -> Result<(), HorridError> {
+        match unsafe { libc::msync(self.inner.as_ptr().cast(), self.map_len, libc::MS_SYNC) } {
+            -1 => Err(HorridError::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Iterate over the records, oldest to newest.
+    pub fn iter(&self) -> Records<'_, N> {
+        Records {
+            ring: self,
+            front: self.head,
+            back: self.tail,
+        }
+    }
+
+    /// The oldest record.
+    pub fn front(&self) -> Option<&[u8; N]> {
+        self.iter().next()
+    }
+
+    /// The newest record.
+    pub fn back(&self) -> Option<&[u8; N]> {
+        self.iter().next_back()
+    }
+
+    /// Sequence number of the oldest record in the ring.
+    /// Equal to [`tail_seq`](Self::tail_seq) when the ring is empty.
+    pub fn head_seq(&self) -> u64 {
+        self.head
+    }
+
+    /// Sequence number the next pushed record will be given.
+    pub fn tail_seq(&self) -> u64 {
+        self.tail
+    }
+
+    /// Get a record by its sequence number.
+    ///
+    /// Returns `None` if the record has been popped or overwritten
+    /// (`seq < head_seq()`), or has not been pushed yet (`seq >= tail_seq()`).
+    pub fn get_by_seq(&self, seq: u64) -> Option<&[u8; N]> {
+        if seq.wrapping_sub(self.head) >= self.len() as u64 {
+            return None;
+        }
+
+        Some(self.record(seq))
+    }
+
+    pub fn len(&self) -> usize {
+        self.tail.wrapping_sub(self.head) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The size of every record in bytes.
+    pub fn record_size(&self) -> usize {
+        N
+    }
+
+    fn write_back(&mut self, record: &[u8; N]) {
+        let seq = self.tail;
+        let slot = self.slot_mut(seq);
+
+        // The checksum goes in last. Dying before it is written leaves a
+        // slot that fails its checksum, rather than a record with torn contents.
+        slot[SLOT_META..].copy_from_slice(record);
+        slot[..8].copy_from_slice(&seq.to_le_bytes());
+        slot[FLAGS..SLOT_META].fill(0);
+        let checksum = crc32(&[&slot[..8], record]);
+        fence(Ordering::Release);
+        slot[8..12].copy_from_slice(&checksum.to_le_bytes());
+
+        self.tail = self.tail.wrapping_add(1);
+    }
+
+    // The tail follows the newest valid record, popped or not, and the head
+    // is the start of the unbroken run of valid, unpopped records leading up to it.
+    fn recover(&mut self) {
+        let newest = (0..self.capacity as u64).filter_map(|index| self.valid_seq(index)).max();
+
+        let newest = match newest {
+            Some(seq) => seq,
+            None => return,
+        };
+
+        let mut head = newest.wrapping_add(1);
+        for _ in 0..self.capacity {
+            let prev = head.wrapping_sub(1);
+            let index = prev % self.capacity as u64;
+            if self.valid_seq(index) != Some(prev) || self.slot_at(index as usize)[FLAGS] == POPPED {
+                break;
+            }
+            head = prev;
+        }
+
+        self.head = head;
+        self.tail = newest.wrapping_add(1);
+    }
+
+    // The sequence number in the slot at `index`, if its checksum holds
+    // and it belongs in that slot.
+    fn valid_seq(&self, index: u64) -> Option<u64> {
+        let slot = self.slot_at(index as usize);
+        let seq = u64::from_le_bytes(slot[..8].try_into().unwrap());
+        let checksum = u32::from_le_bytes(slot[8..12].try_into().unwrap());
+
+        let valid = checksum == crc32(&[&slot[..8], &slot[SLOT_META..]])
+            && seq % self.capacity as u64 == index;
+        valid.then_some(seq)
+    }
+
+    fn write_header(&mut self) {
+        let header = unsafe { slice::from_raw_parts_mut(self.inner.as_ptr(), HEADER_LEN) };
+        header[..8].copy_from_slice(&MAGIC);
+        header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+        header[12..16].copy_from_slice(&(N as u32).to_le_bytes());
+        header[16..24].copy_from_slice(&(self.capacity as u64).to_le_bytes());
+        let checksum = crc32(&[&header[..HEADER_FIELDS]]);
+        header[24..28].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    fn record(&self, seq: u64) -> &[u8; N] {
+        let slot = self.slot_at((seq % self.capacity as u64) as usize);
+        slot[SLOT_META..].try_into().unwrap()
+    }
+
+    fn slot_mut(&mut self, seq: u64) -> &mut [u8] {
+        let slot_len = SLOT_META + N;
+        let offset = HEADER_LEN + (seq % self.capacity as u64) as usize * slot_len;
+        unsafe { slice::from_raw_parts_mut(self.inner.as_ptr().add(offset), slot_len) }
+    }
+
+    fn slot_at(&self, index: usize) -> &[u8] {
+        let slot_len = SLOT_META + N;
+        let offset = HEADER_LEN + index * slot_len;
+        unsafe { slice::from_raw_parts(self.inner.as_ptr().add(offset), slot_len) }
+    }
+}
+
+// Lock the file for as long as it is open, so no other ring can write the
+// records behind the slices this one hands out.
+fn lock(file: &File) -> Result<(), HorridError> {
+    match unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } {
+        -1 => Err(HorridError::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+// Returns the capacity and record size.
+fn read_header(file: &File) -> Result<(usize, usize), HorridError> {
+    use std::os::unix::fs::FileExt;
+
+    let mut header = [0; HEADER_LEN];
+    file.read_exact_at(&mut header, 0).map_err(|_| HorridError::InvalidHeader)?;
+
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    let record_size = u32::from_le_bytes(header[12..16].try_into().unwrap()) as usize;
+    let capacity = u64::from_le_bytes(header[16..24].try_into().unwrap());
+    let checksum = u32::from_le_bytes(header[24..28].try_into().unwrap());
+
+    let valid = header[..8] == MAGIC
+        && checksum == crc32(&[&header[..HEADER_FIELDS]])
+        && version == VERSION
+        && capacity > 0
+        && capacity <= isize::MAX as u64;
+
+    match valid {
+        true => Ok((capacity as usize, record_size)),
+        false => Err(HorridError::InvalidHeader),
+    }
+}
+
+fn io_error(err: std::io::Error) -> HorridError {
+    HorridError::Os(err.raw_os_error().unwrap_or(0))
+}
+
+// CRC-32 (IEEE) over the concatenation of `parts`.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    let mut crc = !0_u32;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc = TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+// The ring owns its mapping outright, and the lock keeps other rings out of
+// the file, so records only change through `&mut self`.
+unsafe impl<const N: usize> Send for PersistentRing<N> {}
+unsafe impl<const N: usize> Sync for PersistentRing<N> {}
+
+impl<const N: usize> Drop for PersistentRing<N> {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.inner.as_ptr().cast(), self.map_len) };
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Records -
+// -----------------------------------------------------------------------------
+/// Iterator over the records in a [`PersistentRing`], oldest to newest.
+pub struct Records<'a, const N: usize> {
+    ring: &'a PersistentRing<N>,
+    front: u64,
+    back: u64,
+}
+
+impl<'a, const N: usize> Iterator for Records<'a, N> {
+    type Item = &'a [u8; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let record = self.ring.record(self.front);
+        self.front = self.front.wrapping_add(1);
+        Some(record)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back.wrapping_sub(self.front) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<const N: usize> DoubleEndedIterator for Records<'_, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back = self.back.wrapping_sub(1);
+        Some(self.ring.record(self.back))
+    }
+}
+
+impl<const N: usize> ExactSizeIterator for Records<'_, N> {}
+
+impl<'a, const N: usize> IntoIterator for &'a PersistentRing<N> {
+    type Item = &'a [u8; N];
+    type IntoIter = Records<'a, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::TempName;
+
+    fn temp_file() -> TempName {
+        let prefix = std::env::temp_dir().join("horrid-persistent");
+        TempName::new(prefix.to_str().unwrap(), |path| {
+            let _ = std::fs::remove_file(path);
+        })
+    }
+
+    fn record(n: u8) -> [u8; 4] {
+        [n; 4]
+    }
+
+    fn slot_offset(index: usize) -> u64 {
+        (HEADER_LEN + index * (SLOT_META + 4)) as u64
+    }
+
+    fn corrupt(path: impl AsRef<Path>, offset: u64) {
+        use std::os::unix::fs::FileExt;
+
+        let file = OpenOptions::new().read(true).write(true).open(path).unwrap();
+        let mut byte = [0];
+        file.read_exact_at(&mut byte, offset).unwrap();
+        file.write_all_at(&[byte[0] ^ 0xFF], offset).unwrap();
+    }
+
+    fn records(ring: &PersistentRing<4>) -> Vec<u8> {
+        ring.iter().map(|r| r[0]).collect()
+    }
+
+    #[test]
+    fn test_push_iter() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 3).unwrap();
+        assert!(ring.is_empty());
+
+        for n in 0..5 {
+            ring.push(record(n));
+        }
+
+        assert_eq!(records(&ring), [2, 3, 4]);
+        assert_eq!(ring.iter().rev().map(|r| r[0]).collect::<Vec<_>>(), [4, 3, 2]);
+        assert_eq!((ring.head_seq(), ring.tail_seq()), (2, 5));
+        assert_eq!(ring.get_by_seq(3), Some(&record(3)));
+        assert_eq!(ring.get_by_seq(1), None);
+        assert_eq!(ring.front(), Some(&record(2)));
+        assert_eq!(ring.back(), Some(&record(4)));
+    }
+
+    #[test]
+    fn test_reopen() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 3).unwrap();
+        for n in 0..5 {
+            ring.push(record(n));
+        }
+        ring.flush().unwrap();
+        drop(ring);
+
+        let mut ring = PersistentRing::<4>::open(&file).unwrap();
+        assert_eq!(records(&ring), [2, 3, 4]);
+        assert_eq!((ring.head_seq(), ring.tail_seq()), (2, 5));
+
+        ring.push(record(5));
+        assert_eq!(records(&ring), [3, 4, 5]);
+    }
+
+    #[test]
+    fn test_reopen_empty() {
+        let file = temp_file();
+        drop(PersistentRing::<4>::create(&file, 3).unwrap());
+
+        let ring = PersistentRing::<4>::open(&file).unwrap();
+        assert!(ring.is_empty());
+        assert_eq!(ring.tail_seq(), 0);
+    }
+
+    #[test]
+    fn test_torn_write_skipped() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 4).unwrap();
+        for n in 0..6 {
+            ring.push(record(n));
+        }
+        drop(ring);
+
+        // Record 5 is in slot 1, as if the process died while writing it
+        corrupt(&file, slot_offset(1) + SLOT_META as u64 + 2);
+
+        // Record 1, which it was overwriting, is gone too
+        let ring = PersistentRing::<4>::open(&file).unwrap();
+        assert_eq!(records(&ring), [2, 3, 4]);
+        assert_eq!((ring.head_seq(), ring.tail_seq()), (2, 5));
+    }
+
+    #[test]
+    fn test_corrupt_middle_record() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 4).unwrap();
+        for n in 0..4 {
+            ring.push(record(n));
+        }
+        drop(ring);
+
+        // Everything older than a bad record is dropped along with it
+        corrupt(&file, slot_offset(1) + 8);
+
+        let ring = PersistentRing::<4>::open(&file).unwrap();
+        assert_eq!(records(&ring), [2, 3]);
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 4).unwrap();
+        for n in 0..4 {
+            ring.push(record(n));
+        }
+        drop(ring);
+
+        // Cut off in the middle of the last slot
+        let f = OpenOptions::new().write(true).open(&file).unwrap();
+        f.set_len(slot_offset(3) + 5).unwrap();
+        drop(f);
+
+        let mut ring = PersistentRing::<4>::open(&file).unwrap();
+        assert_eq!(records(&ring), [0, 1, 2]);
+
+        // The file is back to full size, so the last slot can be used again
+        ring.push(record(3));
+        assert_eq!(records(&ring), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let file = temp_file();
+        drop(PersistentRing::<4>::create(&file, 4).unwrap());
+        corrupt(&file, 17);
+        assert_eq!(PersistentRing::<4>::open(&file).err(), Some(HorridError::InvalidHeader));
+
+        // Too short to hold a header at all
+        std::fs::write(&file, b"HORRIDPR").unwrap();
+        assert_eq!(PersistentRing::<4>::open(&file).err(), Some(HorridError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_open_locked() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 4).unwrap();
+        ring.push(record(1));
+
+        let would_block = Some(HorridError::Os(libc::EWOULDBLOCK));
+        assert_eq!(PersistentRing::<4>::open(&file).err(), would_block);
+        assert_eq!(PersistentRing::<4>::create(&file, 4).err(), would_block);
+
+        // Creating again didn't wipe the records out from under the open ring
+        assert_eq!(records(&ring), [1]);
+        drop(ring);
+
+        let ring = PersistentRing::<4>::open(&file).unwrap();
+        assert_eq!(records(&ring), [1]);
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let file = temp_file();
+        assert_eq!(PersistentRing::<4>::open(&file).err(), Some(HorridError::Os(libc::ENOENT)));
+    }
+
+    #[test]
+    fn test_open_wrong_record_size() {
+        let file = temp_file();
+        drop(PersistentRing::<4>::create(&file, 4).unwrap());
+        assert_eq!(PersistentRing::<8>::open(&file).err(), Some(HorridError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_push_policy() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 2).unwrap();
+        assert_eq!(ring.push(record(0)), None);
+        assert_eq!(ring.push(record(1)), None);
+        assert_eq!(ring.push(record(2)), Some(record(0)));
+
+        ring.set_policy(FullPolicy::Reject);
+        assert_eq!(ring.push(record(3)), Some(record(3)));
+        assert_eq!(ring.try_push(record(3)), Err(record(3)));
+        assert_eq!(records(&ring), [1, 2]);
+    }
+
+    #[test]
+    fn test_pop_survives_reopen() {
+        let file = temp_file();
+        let mut ring = PersistentRing::<4>::create(&file, 3).unwrap();
+        for n in 0..4 {
+            ring.push(record(n));
+        }
+        assert_eq!(ring.pop(), Some(record(1)));
+        drop(ring);
+
+        let mut ring = PersistentRing::<4>::open(&file).unwrap();
+        assert_eq!(records(&ring), [2, 3]);
+        assert_eq!((ring.head_seq(), ring.tail_seq()), (2, 4));
+
+        // Sequence numbers carry on even once everything is popped
+        assert_eq!(ring.pop(), Some(record(2)));
+        assert_eq!(ring.pop(), Some(record(3)));
+        assert_eq!(ring.pop(), None);
+        drop(ring);
+
+        let ring = PersistentRing::<4>::open(&file).unwrap();
+        assert!(ring.is_empty());
+        assert_eq!(ring.tail_seq(), 4);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(&[b"123456789"]), 0xCBF4_3926);
+        assert_eq!(crc32(&[b"1234", b"56789"]), 0xCBF4_3926);
+    }
+}
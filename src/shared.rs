@@ -0,0 +1,523 @@
+//! A ring in named shared memory, for passing values between processes.
+//!
+//! One process calls [`SharedRing::create`] and another opens the same
+//! name with [`SharedRing::open`]. The memory starts with a header holding
+//! a magic number, the layout version, the capacity and the size of a
+//! value, which `open` checks before touching the values.
+//!
+//! Like [`spsc`](crate::spsc), values go in through a [`Producer`] and come
+//! out through a [`Consumer`], which either process takes from its
+//! `SharedRing`. The header records which roles are taken, so taking a
+//! second producer or a second consumer fails until the first one is
+//! dropped. A process that exits without dropping its role keeps it taken,
+//! and the ring has to be unlinked and created again.
+//!
+//! Values are copied in and out as plain bytes, so they must not hold
+//! pointers or anything else that only means something inside one process.
+//!
+//! ```no_run
+//! use horrid_ring_buffer::shared::SharedRing;
+//!
+//! let mut producer = SharedRing::<u64>::create("/horrid-example", 64)?.into_producer()?;
+//! producer.try_push(1).unwrap();
+//!
+//! // In another process
+//! let ring = unsafe { SharedRing::<u64>::open("/horrid-example")? };
+//! let mut consumer = ring.into_consumer()?;
+//! assert_eq!(consumer.pop(), Some(1));
+//! # Ok::<(), horrid_ring_buffer::HorridError>(())
+//! ```
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::ffi::CString;
+
+use crate::atomic::CachePadded;
+use crate::mmap::map;
+use crate::HorridError;
+
+const MAGIC: u32 = u32::from_le_bytes(*b"HRRB");
+const VERSION: u32 = 1;
+
+// -----------------------------------------------------------------------------
+//     - Header -
+// -----------------------------------------------------------------------------
+// Fixed width fields only, so processes built for different targets agree.
+#[repr(C)]
+struct Header {
+    // Written last by `create`, so `open` never sees a half written header
+    magic: AtomicU32,
+    version: u32,
+    capacity: u64,
+    element_size: u64,
+    element_align: u64,
+    // One while a process holds the role, zero otherwise
+    producer: AtomicU32,
+    consumer: AtomicU32,
+    // Number of values popped, only written by the consumer
+    head: CachePadded<AtomicU64>,
+    // Number of values pushed, only written by the producer
+    tail: CachePadded<AtomicU64>,
+}
+
+// The values start after the header, aligned for `T`.
+fn data_offset<T>() -> usize {
+    size_of::<Header>().next_multiple_of(align_of::<T>())
+}
+
+// -----------------------------------------------------------------------------
+//     - Shared ring -
+// -----------------------------------------------------------------------------
+/// A fixed capacity ring in named shared memory.
+///
+/// The name follows the rules of `shm_open`: a leading slash followed by
+/// up to 254 characters, none of them slashes. The memory stays around
+/// until [`unlink`](Self::unlink) is called, even once no process has it open.
+///
+/// Values are pushed and popped through the roles handed out by
+/// [`into_producer`](Self::into_producer) and [`into_consumer`](Self::into_consumer).
+pub struct SharedRing<T> {
+    inner: NonNull<u8>,
+    map_len: usize,
+    capacity: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> SharedRing<T> {
+    /// Create the shared memory and set up an empty ring in it.
+    ///
+    /// Fails with [`HorridError::Os`] if the name is already taken.
+    pub fn create(name: &str, capacity: usize) -> Result<Self, HorridError> {
+        if capacity == 0 {
+            return Err(HorridError::ZeroCapacity);
+        }
+
+        let map_len = size_of::<T>()
+            .checked_mul(capacity)
+            .and_then(|size| size.checked_add(data_offset::<T>()))
+            .filter(|len| *len <= isize::MAX as usize)
+            .ok_or(HorridError::CapacityOverflow)?;
+
+        let name = shm_name(name)?;
+        let flags = libc::O_RDWR | libc::O_CREAT | libc::O_EXCL;
+        let inner = unsafe {
+            let fd = libc::shm_open(name.as_ptr(), flags, 0o600);
+            if fd == -1 {
+                return Err(HorridError::last_os_error());
+            }
+
+            let mapped = if libc::ftruncate(fd, map_len as libc::off_t) == -1 {
+                Err(HorridError::last_os_error())
+            } else {
+                map(fd, map_len)
+            };
+            libc::close(fd);
+
+            if mapped.is_err() {
+                libc::shm_unlink(name.as_ptr());
+            }
+            mapped?
+        };
+
+        let header = Header {
+            magic: AtomicU32::new(0),
+            version: VERSION,
+            capacity: capacity as u64,
+            element_size: size_of::<T>() as u64,
+            element_align: align_of::<T>() as u64,
+            producer: AtomicU32::new(0),
+            consumer: AtomicU32::new(0),
+            head: CachePadded(AtomicU64::new(0)),
+            tail: CachePadded(AtomicU64::new(0)),
+        };
+
+        let ring = Self {
+            inner,
+            map_len,
+            capacity,
+            _marker: PhantomData,
+        };
+
+        unsafe { ptr::write(ring.inner.as_ptr().cast::<Header>(), header) };
+        ring.header().magic.store(MAGIC, Ordering::Release);
+        Ok(ring)
+    }
+
+    /// Open a ring another process created.
+    ///
+    /// Fails with [`HorridError::InvalidHeader`] if the memory does not hold
+    /// a ring, was made by a different version of this crate, or holds
+    /// values of a different size or alignment than `T`.
+    ///
+    /// # Safety
+    ///
+    /// The ring must have been created with the same `T`. The header can
+    /// tell a `u32` from a `u64`, but not from an `f32`.
+    pub unsafe fn open(name: &str) -> Result<Self, HorridError> {
+        let name = shm_name(name)?;
+        let fd = libc::shm_open(name.as_ptr(), libc::O_RDWR, 0);
+        if fd == -1 {
+            return Err(HorridError::last_os_error());
+        }
+
+        let mut stat: libc::stat = core::mem::zeroed();
+        let mapped = if libc::fstat(fd, &mut stat) == -1 {
+            Err(HorridError::last_os_error())
+        } else if (stat.st_size as u64) < data_offset::<T>() as u64 {
+            Err(HorridError::InvalidHeader)
+        } else {
+            map(fd, stat.st_size as usize)
+        };
+        libc::close(fd);
+
+        let mut ring = Self {
+            inner: mapped?,
+            map_len: stat.st_size as usize,
+            capacity: 0,
+            _marker: PhantomData,
+        };
+
+        ring.capacity = ring.validate()?;
+        Ok(ring)
+    }
+
+    // Check the header against `T` and the size of the mapping,
+    // returning the capacity.
+    fn validate(&self) -> Result<usize, HorridError> {
+        let header = self.header();
+        let valid = header.magic.load(Ordering::Acquire) == MAGIC
+            && header.version == VERSION
+            && header.element_size == size_of::<T>() as u64
+            && header.element_align == align_of::<T>() as u64
+            && header.capacity > 0;
+
+        if !valid {
+            return Err(HorridError::InvalidHeader);
+        }
+
+        // The values have to fit in what was mapped
+        let needed = header
+            .capacity
+            .checked_mul(header.element_size)
+            .and_then(|size| size.checked_add(data_offset::<T>() as u64));
+
+        match needed {
+            Some(needed) if needed <= self.map_len as u64 => Ok(header.capacity as usize),
+            _ => Err(HorridError::InvalidHeader),
+        }
+    }
+
+    /// Remove the name, so it can't be opened again. Processes that
+    /// already have the ring open can keep using it.
+    pub fn unlink(name: &str) -> Result<(), HorridError> {
+        let name = shm_name(name)?;
+        match unsafe { libc::shm_unlink(name.as_ptr()) } {
+            -1 => Err(HorridError::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Take the producer role, the only one allowed to push.
+    ///
+    /// Fails with [`HorridError::Os`]`(EBUSY)` if a producer,
+    /// in this process or another, already has it.
+    pub fn into_producer(self) -> Result<Producer<T>, HorridError> {
+        self.claim(&self.header().producer)?;
+        Ok(Producer { ring: self })
+    }
+
+    /// Take the consumer role, the only one allowed to pop.
+    ///
+    /// Fails with [`HorridError::Os`]`(EBUSY)` if a consumer,
+    /// in this process or another, already has it.
+    pub fn into_consumer(self) -> Result<Consumer<T>, HorridError> {
+        self.claim(&self.header().consumer)?;
+        Ok(Consumer { ring: self })
+    }
+
+    fn claim(&self, role: &AtomicU32) -> Result<(), HorridError> {
+        match role.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(HorridError::Os(libc::EBUSY)),
+        }
+    }
+
+    /// Number of values in the ring. The other process may be pushing or
+    /// popping concurrently, so this can be out of date as soon as it returns.
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let head = header.head.load(Ordering::Acquire);
+        let tail = header.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(head) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn slot(&self, pos: u64) -> *mut T {
+        let index = (pos % self.capacity as u64) as usize;
+        unsafe { self.inner.as_ptr().add(data_offset::<T>()).cast::<T>().add(index) }
+    }
+}
+
+impl<T> SharedRing<T> {
+    fn header(&self) -> &Header {
+        unsafe { self.inner.cast::<Header>().as_ref() }
+    }
+}
+
+// Only the positions and roles are shared between processes, and those are atomic.
+unsafe impl<T: Send> Send for SharedRing<T> {}
+
+impl<T> Drop for SharedRing<T> {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.inner.as_ptr().cast(), self.map_len) };
+    }
+}
+
+fn shm_name(name: &str) -> Result<CString, HorridError> {
+    CString::new(name).map_err(|_| HorridError::Os(libc::EINVAL))
+}
+
+// -----------------------------------------------------------------------------
+//     - Producer -
+// -----------------------------------------------------------------------------
+/// The pushing role of a [`SharedRing`]. Dropping it gives the role back.
+pub struct Producer<T> {
+    ring: SharedRing<T>,
+}
+
+impl<T: Copy> Producer<T> {
+    /// Push a value if there is room for it.
+    pub fn try_push(&mut self, val: T) -> Result<(), T> {
+        let ring = &self.ring;
+        let header = ring.header();
+        let tail = header.tail.load(Ordering::Relaxed);
+        let head = header.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == ring.capacity as u64 {
+            return Err(val);
+        }
+
+        unsafe { ptr::write(ring.slot(tail), val) };
+        header.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// See [`SharedRing::len`].
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.ring.header().producer.store(0, Ordering::Release);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Consumer -
+// -----------------------------------------------------------------------------
+/// The popping role of a [`SharedRing`]. Dropping it gives the role back.
+pub struct Consumer<T> {
+    ring: SharedRing<T>,
+}
+
+impl<T: Copy> Consumer<T> {
+    /// Remove and return the oldest value.
+    pub fn pop(&mut self) -> Option<T> {
+        let ring = &self.ring;
+        let header = ring.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let val = unsafe { ptr::read(ring.slot(head)) };
+        header.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(val)
+    }
+
+    /// See [`SharedRing::len`].
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.ring.header().consumer.store(0, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::TempName;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const CHILD_ENV: &str = "HORRID_SHARED_RING_CHILD";
+
+    fn temp_name() -> TempName {
+        TempName::new("/horrid-ring-test", |name| {
+            let _ = SharedRing::<u8>::unlink(name);
+        })
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let name = temp_name();
+        let mut producer = SharedRing::<u64>::create(&name, 2).unwrap().into_producer().unwrap();
+        let ring = unsafe { SharedRing::<u64>::open(&name) }.unwrap();
+        let mut consumer = ring.into_consumer().unwrap();
+
+        producer.try_push(1).unwrap();
+        producer.try_push(2).unwrap();
+        assert_eq!(producer.try_push(3), Err(3));
+        assert_eq!(consumer.len(), 2);
+
+        assert_eq!(consumer.pop(), Some(1));
+        producer.try_push(3).unwrap();
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), Some(3));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_roles_are_exclusive() {
+        let name = temp_name();
+        let open = || unsafe { SharedRing::<u8>::open(&name) }.unwrap();
+        let producer = SharedRing::<u8>::create(&name, 1).unwrap().into_producer().unwrap();
+        let consumer = open().into_consumer().unwrap();
+
+        assert_eq!(open().into_producer().err(), Some(HorridError::Os(libc::EBUSY)));
+        assert_eq!(open().into_consumer().err(), Some(HorridError::Os(libc::EBUSY)));
+
+        // Dropping a role gives it back
+        drop(producer);
+        drop(consumer);
+        open().into_producer().unwrap();
+        open().into_consumer().unwrap();
+    }
+
+    #[test]
+    fn test_create_existing_name() {
+        let name = temp_name();
+        let _ring = SharedRing::<u8>::create(&name, 1).unwrap();
+        let err = SharedRing::<u8>::create(&name, 1).err();
+        assert_eq!(err, Some(HorridError::Os(libc::EEXIST)));
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let err = unsafe { SharedRing::<u8>::open(&temp_name()) }.err();
+        assert_eq!(err, Some(HorridError::Os(libc::ENOENT)));
+    }
+
+    #[test]
+    fn test_open_wrong_type() {
+        let name = temp_name();
+        let _ring = SharedRing::<u32>::create(&name, 4).unwrap();
+        let err = unsafe { SharedRing::<u64>::open(&name) }.err();
+        assert_eq!(err, Some(HorridError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_open_corrupt_header() {
+        let name = temp_name();
+        let ring = SharedRing::<u32>::create(&name, 4).unwrap();
+
+        // A version from the future
+        unsafe { (*ring.inner.as_ptr().cast::<Header>()).version = VERSION + 1 };
+        let err = unsafe { SharedRing::<u32>::open(&name) }.err();
+        assert_eq!(err, Some(HorridError::InvalidHeader));
+
+        // A capacity larger than the memory
+        unsafe {
+            let header = ring.inner.as_ptr().cast::<Header>();
+            (*header).version = VERSION;
+            (*header).capacity = 1000;
+        }
+        let err = unsafe { SharedRing::<u32>::open(&name) }.err();
+        assert_eq!(err, Some(HorridError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_across_processes() {
+        let name = temp_name();
+        let ring = SharedRing::<u64>::create(&name, 8).unwrap();
+        let mut producer = ring.into_producer().unwrap();
+
+        // Run `child_consumer` below in a new process
+        let mut child = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "shared::test::child_consumer", "--nocapture"])
+            .env(CHILD_ENV, &*name)
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        // Give up rather than hang if the child dies or never gets going
+        let deadline = Instant::now() + Duration::from_secs(30);
+        for i in 0..10_000 {
+            while producer.try_push(i).is_err() {
+                if let Some(status) = child.try_wait().unwrap() {
+                    panic!("child exited with {} before reading everything", status);
+                }
+                if Instant::now() > deadline {
+                    child.kill().unwrap();
+                    panic!("child stopped reading");
+                }
+                thread::yield_now();
+            }
+        }
+
+        assert!(child.wait().unwrap().success());
+    }
+
+    // Only does anything when spawned by `test_across_processes`
+    #[test]
+    fn child_consumer() {
+        let name = match std::env::var(CHILD_ENV) {
+            Ok(name) => name,
+            Err(_) => return,
+        };
+
+        let ring = unsafe { SharedRing::<u64>::open(&name) }.unwrap();
+        let mut consumer = ring.into_consumer().unwrap();
+        let mut next = 0;
+        while next < 10_000 {
+            match consumer.pop() {
+                Some(i) => {
+                    assert_eq!(i, next);
+                    next += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+    }
+}
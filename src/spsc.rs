@@ -0,0 +1,560 @@
+//! Lock-free single producer, single consumer halves of a [`HorridRing`].
+//!
+//! ```
+//! use horrid_ring_buffer::HorridRing;
+//!
+//! let (mut producer, mut consumer) = HorridRing::with_capacity(4).split();
+//! let handle = std::thread::spawn(move || {
+//!     for i in 0..100 {
+//!         producer.push(i).unwrap();
+//!     }
+//! });
+//!
+//! let mut next = 0;
+//! while next < 100 {
+//!     match consumer.pop() {
+//!         Some(i) => {
+//!             assert_eq!(i, next);
+//!             next += 1;
+//!         }
+//!         None => std::thread::yield_now(),
+//!     }
+//! }
+//! handle.join().unwrap();
+//! ```
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+use crate::atomic::{backoff, Arc, AtomicBool, AtomicUsize, CachePadded, Ordering};
+use crate::{Heap, HorridRing, Storage};
+
+// -----------------------------------------------------------------------------
+//     - Shared -
+// -----------------------------------------------------------------------------
+// Positions run from zero to twice the capacity before wrapping,
+// so a full ring and an empty ring can be told apart without a length.
+struct Shared<T> {
+    // Next position to pop, only written by the consumer
+    head: CachePadded<AtomicUsize>,
+    // Next position to push, only written by the producer
+    tail: CachePadded<AtomicUsize>,
+    // Set when either half is dropped
+    disconnected: AtomicBool,
+    storage: Heap<T>,
+    // Loom can't see accesses through raw pointers, so every access to a
+    // slot is also made to its cell here, where loom can check the ordering
+    #[cfg(loom)]
+    cells: alloc::boxed::Box<[loom::cell::UnsafeCell<()>]>,
+}
+
+impl<T> Shared<T> {
+    fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    fn len(&self, head: usize, tail: usize) -> usize {
+        if tail >= head {
+            tail - head
+        } else {
+            2 * self.capacity() - head + tail
+        }
+    }
+
+    fn slot(&self, pos: usize) -> usize {
+        if pos < self.capacity() {
+            pos
+        } else {
+            pos - self.capacity()
+        }
+    }
+
+    fn advance(&self, pos: usize, by: usize) -> usize {
+        let pos = pos + by;
+        if pos < 2 * self.capacity() {
+            pos
+        } else {
+            pos - 2 * self.capacity()
+        }
+    }
+
+    // The storage pointer is only ever written through at slots the
+    // caller has exclusive access to, as decided by `head` and `tail`.
+    fn ptr(&self, pos: usize) -> *mut T {
+        unsafe { self.storage.as_ptr().add(self.slot(pos)) as *mut T }
+    }
+
+    // Called before writing `count` slots from `pos` on.
+    fn note_write(&self, pos: usize, count: usize) {
+        #[cfg(loom)]
+        for i in 0..count {
+            self.cells[self.slot(self.advance(pos, i))].with_mut(|_| ());
+        }
+
+        #[cfg(not(loom))]
+        let _ = (pos, count);
+    }
+
+    // Called before reading `count` slots from `pos` on.
+    fn note_read(&self, pos: usize, count: usize) {
+        #[cfg(loom)]
+        for i in 0..count {
+            self.cells[self.slot(self.advance(pos, i))].with(|_| ());
+        }
+
+        #[cfg(not(loom))]
+        let _ = (pos, count);
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Relaxed);
+        while head != tail {
+            unsafe { ptr::drop_in_place(self.ptr(head)) };
+            head = self.advance(head, 1);
+        }
+    }
+}
+
+// Values are moved between threads through the storage, but only one
+// thread at a time has access to any given slot.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+// -----------------------------------------------------------------------------
+//     - Split -
+// -----------------------------------------------------------------------------
+impl<T> HorridRing<T> {
+    /// Split the ring into a producer and a consumer that can be sent to
+    /// different threads. Values already in the ring stay there.
+    ///
+    /// The halves never overwrite: [`Producer::push`] waits for the
+    /// consumer to make room, like [`FullPolicy::Block`](crate::FullPolicy::Block).
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let ring = ManuallyDrop::new(self);
+        let len = ring.len();
+        // The ring is never dropped, so the storage is only owned once
+        let storage = unsafe { ptr::read(&ring.storage) };
+
+        let shared = Arc::new(Shared {
+            head: CachePadded(AtomicUsize::new(ring.read)),
+            tail: CachePadded(AtomicUsize::new(ring.read + len)),
+            disconnected: AtomicBool::new(false),
+            #[cfg(loom)]
+            cells: (0..storage.capacity()).map(|_| loom::cell::UnsafeCell::new(())).collect(),
+            storage,
+        });
+
+        let producer = Producer { shared: shared.clone() };
+        let consumer = Consumer { shared };
+        (producer, consumer)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Producer -
+// -----------------------------------------------------------------------------
+/// The pushing half of a split [`HorridRing`].
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Producer<T> {
+    /// Push a value, waiting for the consumer to make room if the ring is full.
+    ///
+    /// Gives the value back if the consumer has been dropped.
+    pub fn push(&mut self, mut val: T) -> Result<(), T> {
+        loop {
+            if self.is_disconnected() {
+                return Err(val);
+            }
+
+            match self.try_push(val) {
+                Ok(()) => return Ok(()),
+                Err(v) => val = v,
+            }
+            backoff();
+        }
+    }
+
+    /// Push a value if there is room for it.
+    pub fn try_push(&mut self, val: T) -> Result<(), T> {
+        let shared = &self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        if shared.len(head, tail) == shared.capacity() {
+            return Err(val);
+        }
+
+        shared.note_write(tail, 1);
+        unsafe { ptr::write(shared.ptr(tail), val) };
+        shared.tail.store(shared.advance(tail, 1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Copy as many values from `src` as there is room for,
+    /// returning how many were copied.
+    pub fn push_slice(&mut self, src: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let shared = &self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        let len = src.len().min(shared.capacity() - shared.len(head, tail));
+
+        // At most two copies: up to the end of the storage, then from the start
+        let first = len.min(shared.capacity() - shared.slot(tail));
+        shared.note_write(tail, len);
+        unsafe {
+            ptr::copy_nonoverlapping(src.as_ptr(), shared.ptr(tail), first);
+            ptr::copy_nonoverlapping(src.as_ptr().add(first), shared.ptr(0), len - first);
+        }
+
+        shared.tail.store(shared.advance(tail, len), Ordering::Release);
+        len
+    }
+
+    /// Number of values in the ring. The consumer may be popping
+    /// concurrently, so this can be out of date as soon as it returns.
+    pub fn len(&self) -> usize {
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        let head = self.shared.head.load(Ordering::Acquire);
+        self.shared.len(head, tail)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Whether the consumer has been dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.disconnected.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::Release);
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Consumer -
+// -----------------------------------------------------------------------------
+/// The popping half of a split [`HorridRing`].
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Consumer<T> {
+    /// Remove and return the oldest value.
+    pub fn pop(&mut self) -> Option<T> {
+        let shared = &self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        shared.note_read(head, 1);
+        let val = unsafe { ptr::read(shared.ptr(head)) };
+        shared.head.store(shared.advance(head, 1), Ordering::Release);
+        Some(val)
+    }
+
+    /// Copy as many values into `dst` as are available,
+    /// returning how many were copied.
+    pub fn pop_slice(&mut self, dst: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let shared = &self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        let len = dst.len().min(shared.len(head, tail));
+
+        // At most two copies: up to the end of the storage, then from the start
+        let first = len.min(shared.capacity() - shared.slot(head));
+        shared.note_read(head, len);
+        unsafe {
+            ptr::copy_nonoverlapping(shared.ptr(head), dst.as_mut_ptr(), first);
+            ptr::copy_nonoverlapping(shared.ptr(0), dst.as_mut_ptr().add(first), len - first);
+        }
+
+        shared.head.store(shared.advance(head, len), Ordering::Release);
+        len
+    }
+
+    /// Number of values in the ring. The producer may be pushing
+    /// concurrently, so this can be out of date as soon as it returns.
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.load(Ordering::Relaxed);
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        self.shared.len(head, tail)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Whether the producer has been dropped. Values it pushed
+    /// before that can still be popped.
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.disconnected.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(all(test, not(loom), feature = "std"))]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_halves_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Producer<String>>();
+        assert_send::<Consumer<String>>();
+    }
+
+    #[test]
+    fn test_split_keeps_values() {
+        let mut rb = HorridRing::with_capacity(3);
+        for i in 0..5 {
+            rb.push(i);
+        }
+
+        let (mut producer, mut consumer) = rb.split();
+        assert_eq!(consumer.len(), 3);
+        assert_eq!(producer.try_push(5), Err(5));
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(producer.try_push(5), Ok(()));
+        assert_eq!(consumer.pop(), Some(3));
+        assert_eq!(consumer.pop(), Some(4));
+        assert_eq!(consumer.pop(), Some(5));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_slices_wrap() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(4).split();
+        assert_eq!(producer.push_slice(&[1, 2, 3]), 3);
+
+        let mut buf = [0; 2];
+        assert_eq!(consumer.pop_slice(&mut buf), 2);
+        assert_eq!(buf, [1, 2]);
+
+        assert_eq!(producer.push_slice(&[4, 5, 6, 7]), 3);
+        let mut buf = [0; 8];
+        assert_eq!(consumer.pop_slice(&mut buf), 4);
+        assert_eq!(&buf[..4], &[3, 4, 5, 6]);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_drops_remaining() {
+        let val = std::rc::Rc::new(());
+        {
+            let mut rb = HorridRing::with_capacity(2);
+            rb.push(val.clone());
+            let (mut producer, consumer) = rb.split();
+            producer.push(val.clone()).unwrap();
+            drop(producer);
+            assert_eq!(consumer.len(), 2);
+        }
+        assert_eq!(std::rc::Rc::strong_count(&val), 1);
+    }
+
+    #[test]
+    fn test_push_after_consumer_dropped() {
+        let (mut producer, consumer) = HorridRing::with_capacity(1).split();
+        producer.push(1).unwrap();
+        assert!(!producer.is_disconnected());
+
+        // The ring is full and nothing will ever pop, so waiting would never end
+        drop(consumer);
+        assert!(producer.is_disconnected());
+        assert_eq!(producer.push(2), Err(2));
+    }
+
+    #[test]
+    fn test_pop_after_producer_dropped() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(2).split();
+        producer.push(1).unwrap();
+        drop(producer);
+
+        assert!(consumer.is_disconnected());
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_threads() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(7).split();
+        let handle = thread::spawn(move || {
+            for i in 0..10_000 {
+                producer.push(i.to_string()).unwrap();
+            }
+        });
+
+        let mut next = 0;
+        while next < 10_000 {
+            match consumer.pop() {
+                Some(s) => {
+                    assert_eq!(s, next.to_string());
+                    next += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        handle.join().unwrap();
+        assert!(consumer.pop().is_none());
+    }
+
+    #[test]
+    fn test_threads_slices() {
+        let (mut producer, mut consumer) = HorridRing::with_capacity(16).split();
+        let data = (0..100_000u32).collect::<Vec<_>>();
+        let expected = data.clone();
+
+        let handle = thread::spawn(move || {
+            let mut src = &data[..];
+            while !src.is_empty() {
+                match producer.push_slice(&src[..src.len().min(5)]) {
+                    0 => thread::yield_now(),
+                    n => src = &src[n..],
+                }
+            }
+        });
+
+        let mut received = Vec::new();
+        let mut buf = [0; 9];
+        while received.len() < expected.len() {
+            match consumer.pop_slice(&mut buf) {
+                0 => thread::yield_now(),
+                n => received.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        handle.join().unwrap();
+        assert_eq!(received, expected);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::*;
+    use loom::thread;
+
+    // Run with `RUSTFLAGS="--cfg loom" cargo test --release spsc::loom_test`
+
+    #[test]
+    fn loom_push_pop() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = HorridRing::with_capacity(2).split();
+            let handle = thread::spawn(move || {
+                for i in 0..3 {
+                    producer.push(i).unwrap();
+                }
+            });
+
+            let mut next = 0;
+            while next < 3 {
+                match consumer.pop() {
+                    Some(i) => {
+                        assert_eq!(i, next);
+                        next += 1;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+
+            handle.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn loom_slices() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = HorridRing::with_capacity(2).split();
+            let handle = thread::spawn(move || {
+                let mut src = &[1, 2, 3][..];
+                while !src.is_empty() {
+                    match producer.push_slice(src) {
+                        0 => thread::yield_now(),
+                        n => src = &src[n..],
+                    }
+                }
+            });
+
+            let mut received = Vec::new();
+            let mut buf = [0; 2];
+            while received.len() < 3 {
+                match consumer.pop_slice(&mut buf) {
+                    0 => thread::yield_now(),
+                    n => received.extend_from_slice(&buf[..n]),
+                }
+            }
+
+            handle.join().unwrap();
+            assert_eq!(received, vec![1, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn loom_drop_remaining() {
+        loom::model(|| {
+            let (mut producer, consumer) = HorridRing::with_capacity(2).split();
+            let val = Arc::new(());
+            let handle = {
+                let val = val.clone();
+                thread::spawn(move || {
+                    // Either pushed before the consumer was dropped, or handed back
+                    let _ = producer.push(val);
+                })
+            };
+
+            drop(consumer);
+            handle.join().unwrap();
+            assert_eq!(Arc::strong_count(&val), 1);
+        });
+    }
+
+    // The same handoff as `try_push`, but publishing the value with a
+    // Relaxed store. If loom doesn't catch this, the tests above prove nothing.
+    #[test]
+    #[should_panic(expected = "Causality violation")]
+    fn loom_catches_relaxed_publish() {
+        loom::model(|| {
+            let (producer, mut consumer) = HorridRing::with_capacity(2).split();
+            let handle = thread::spawn(move || {
+                let shared = &producer.shared;
+                shared.note_write(0, 1);
+                unsafe { ptr::write(shared.ptr(0), 1) };
+                shared.tail.store(1, Ordering::Relaxed);
+            });
+
+            while consumer.pop().is_none() {
+                thread::yield_now();
+            }
+
+            handle.join().unwrap();
+        });
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(feature = "alloc")]
+use alloc::alloc::{alloc_zeroed, dealloc, Layout};
+use core::mem::MaybeUninit;
+#[cfg(feature = "alloc")]
+use core::ptr::NonNull;
+
+#[cfg(feature = "alloc")]
+use crate::HorridError;
+
+// -----------------------------------------------------------------------------
+//     - Storage -
+// -----------------------------------------------------------------------------
+/// The memory a [`HorridRing`](crate::HorridRing) keeps its values in.
+///
+/// The storage only hands out memory. Keeping track of which slots are
+/// initialized, and dropping their values, is up to the ring.
+///
+/// This trait is sealed: only the storages in this crate implement it.
+///
+/// # Safety
+///
+/// Both pointers must point to `capacity()` contiguous, properly aligned
+/// slots of `T`, and the slots must keep their contents for as long as the
+/// storage is alive.
+///
+/// The memory must start out zeroed, so that the free space of a byte ring
+/// can be read into without initializing it first.
+pub unsafe trait Storage<T>: sealed::Sealed {
+    fn as_ptr(&self) -> *const T;
+
+    fn as_mut_ptr(&mut self) -> *mut T;
+
+    fn capacity(&self) -> usize;
+}
+
+pub(crate) mod sealed {
+    // Keeps `Storage` from being implemented outside the crate, so its
+    // safety contract can change without breaking anyone.
+    pub trait Sealed {}
+}
+
+// -----------------------------------------------------------------------------
+//     - Heap -
+// -----------------------------------------------------------------------------
+/// Storage on the heap, sized at runtime.
+///
+/// Can only be created with the `alloc` feature enabled.
+pub struct Heap<T> {
+    inner: *mut T,
+    capacity: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Heap<T> {
+    pub(crate) fn try_with_capacity(capacity: usize) -> Result<Self, HorridError> {
+        if capacity == 0 {
+            return Err(HorridError::ZeroCapacity);
+        }
+
+        // Keeps `read + offset` from overflowing, even for zero sized values
+        if capacity > isize::MAX as usize {
+            return Err(HorridError::CapacityOverflow);
+        }
+
+        let layout = Layout::array::<T>(capacity).map_err(|_| HorridError::CapacityOverflow)?;
+
+        // Zero sized values take no memory, and allocating nothing is undefined
+        // behaviour. Any well aligned pointer will do for them.
+        if layout.size() == 0 {
+            let inner = NonNull::dangling().as_ptr();
+            return Ok(Self { inner, capacity });
+        }
+
+        let mem = unsafe { alloc_zeroed(layout) };
+        if mem.is_null() {
+            return Err(HorridError::AllocFailed);
+        }
+
+        let inner = mem.cast::<T>();
+        Ok(Self { inner, capacity })
+    }
+}
+
+impl<T> sealed::Sealed for Heap<T> {}
+
+unsafe impl<T> Storage<T> for Heap<T> {
+    fn as_ptr(&self) -> *const T {
+        self.inner
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.inner
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+// The heap storage owns its values just like a `Box<[T]>` would.
+unsafe impl<T: Send> Send for Heap<T> {}
+unsafe impl<T: Sync> Sync for Heap<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T> Drop for Heap<T> {
+    fn drop(&mut self) {
+        unsafe {
+            // Can't fail, the same layout was used to allocate
+            let layout = Layout::array::<T>(self.capacity).unwrap_unchecked();
+            if layout.size() > 0 {
+                dealloc(self.inner.cast::<u8>(), layout);
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Inline -
+// -----------------------------------------------------------------------------
+/// Storage inside the ring itself, sized at compile time.
+pub struct Inline<T, const N: usize> {
+    inner: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> Inline<T, N> {
+    // Evaluated when `new` is instantiated, so `N == 0` is a compile error
+    // rather than a division by zero at runtime.
+    const NON_ZERO: () = assert!(N > 0, "capacity must be at least one");
+
+    pub(crate) const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::NON_ZERO;
+        Self {
+            inner: [const { MaybeUninit::zeroed() }; N],
+        }
+    }
+}
+
+impl<T, const N: usize> sealed::Sealed for Inline<T, N> {}
+
+unsafe impl<T, const N: usize> Storage<T> for Inline<T, N> {
+    fn as_ptr(&self) -> *const T {
+        self.inner.as_ptr().cast()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.inner.as_mut_ptr().cast()
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
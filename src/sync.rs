@@ -0,0 +1,403 @@
+//! Blocking bounded channel: a [`HorridRing`] behind a `Mutex`, with
+//! `Condvar`s to wake senders when there is room and receivers when there
+//! are values.
+//!
+//! ```
+//! use horrid_ring_buffer::sync;
+//!
+//! let (tx, rx) = sync::channel(2);
+//! let handle = std::thread::spawn(move || {
+//!     for i in 0..10 {
+//!         tx.send(i).unwrap();
+//!     }
+//! });
+//!
+//! let received = std::iter::from_fn(|| rx.recv().ok()).collect::<Vec<_>>();
+//! assert_eq!(received, (0..10).collect::<Vec<_>>());
+//! handle.join().unwrap();
+//! ```
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{FullPolicy, HorridError, HorridRing};
+
+// -----------------------------------------------------------------------------
+//     - Channel -
+// -----------------------------------------------------------------------------
+/// Create a channel where senders wait for room when it is full.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero, see [`HorridRing::with_capacity`].
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    channel_with_policy(capacity, FullPolicy::Block)
+}
+
+/// Create a channel with a [`FullPolicy`] other than waiting for room.
+///
+/// With `OverwriteOldest` the evicted values are dropped.
+/// With `Reject` a send to a full channel fails with [`HorridError::Full`].
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero, see [`HorridRing::with_capacity`].
+pub fn channel_with_policy<T>(capacity: usize, policy: FullPolicy) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            ring: HorridRing::with_policy(capacity, policy),
+            senders: 1,
+            receivers: 1,
+            closed: false,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+
+    let sender = Sender { shared: shared.clone() };
+    (sender, Receiver { shared })
+}
+
+struct State<T> {
+    ring: HorridRing<T>,
+    senders: usize,
+    receivers: usize,
+    closed: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    // A panic while holding the lock can't leave the ring half updated,
+    // so a poisoned lock is still good to use.
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Wait on `condvar` until woken or `deadline` passes.
+    // Returns `None` once the deadline has passed.
+    fn wait<'a>(
+        &self,
+        condvar: &Condvar,
+        state: MutexGuard<'a, State<T>>,
+        deadline: Option<Instant>,
+    ) -> Option<MutexGuard<'a, State<T>>> {
+        match deadline {
+            None => Some(condvar.wait(state).unwrap_or_else(|e| e.into_inner())),
+            Some(deadline) => {
+                let timeout = deadline.checked_duration_since(Instant::now())?;
+                let (state, _) = condvar.wait_timeout(state, timeout).unwrap_or_else(|e| e.into_inner());
+                Some(state)
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    fn send(&self, mut val: T, deadline: Option<Instant>) -> Result<(), SendError<T>> {
+        let mut state = self.lock();
+
+        let evicted = loop {
+            if state.closed || state.receivers == 0 {
+                return Err(SendError::new(val, HorridError::Disconnected));
+            }
+
+            match state.ring.policy() {
+                FullPolicy::OverwriteOldest => break state.ring.push(val),
+                FullPolicy::Reject => match state.ring.try_push(val) {
+                    Ok(()) => break None,
+                    Err(val) => return Err(SendError::new(val, HorridError::Full)),
+                },
+                FullPolicy::Block => match state.ring.try_push(val) {
+                    Ok(()) => break None,
+                    Err(v) => val = v,
+                },
+            }
+
+            // Checking for room again after a timeout means a wakeup that
+            // races with the timeout is never lost.
+            state = match self.wait(&self.not_full, state, deadline) {
+                Some(state) => state,
+                None => return Err(SendError::new(val, HorridError::Timeout)),
+            };
+        };
+
+        drop(state);
+        self.not_empty.notify_one();
+        drop(evicted);
+        Ok(())
+    }
+
+    fn recv(&self, deadline: Option<Instant>) -> Result<T, HorridError> {
+        let mut state = self.lock();
+
+        let val = loop {
+            if let Some(val) = state.ring.pop() {
+                break val;
+            }
+
+            if state.closed || state.senders == 0 {
+                return Err(HorridError::Disconnected);
+            }
+
+            state = self.wait(&self.not_empty, state, deadline).ok_or(HorridError::Timeout)?;
+        };
+
+        drop(state);
+        self.not_full.notify_one();
+        Ok(val)
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Send error -
+// -----------------------------------------------------------------------------
+/// A failed send, handing back the value that could not be sent.
+pub struct SendError<T> {
+    pub value: T,
+    pub error: HorridError,
+}
+
+impl<T> SendError<T> {
+    fn new(value: T, error: HorridError) -> Self {
+        Self { value, error }
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").field("error", &self.error).finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "send failed: {}", self.error)
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+// -----------------------------------------------------------------------------
+//     - Sender -
+// -----------------------------------------------------------------------------
+/// The sending side of a [`channel`]. Clone it for more senders.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Send a value, waiting for room if the channel is full.
+    ///
+    /// Fails with [`HorridError::Disconnected`] if the channel is closed or
+    /// every receiver is gone.
+    pub fn send(&self, val: T) -> Result<(), SendError<T>> {
+        self.shared.send(val, None)
+    }
+
+    /// Like [`send`](Self::send), but fails with [`HorridError::Timeout`]
+    /// if there is still no room after `timeout`.
+    pub fn send_timeout(&self, val: T, timeout: Duration) -> Result<(), SendError<T>> {
+        self.shared.send(val, Instant::now().checked_add(timeout))
+    }
+
+    /// Close the channel for every sender and receiver.
+    /// Values already sent can still be received.
+    pub fn close(&self) {
+        self.shared.close();
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//     - Receiver -
+// -----------------------------------------------------------------------------
+/// The receiving side of a [`channel`]. Clone it for more receivers.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receive the oldest value, waiting for one if the channel is empty.
+    ///
+    /// Fails with [`HorridError::Disconnected`] once the channel is empty and
+    /// either closed or every sender is gone.
+    pub fn recv(&self) -> Result<T, HorridError> {
+        self.shared.recv(None)
+    }
+
+    /// Like [`recv`](Self::recv), but fails with [`HorridError::Timeout`]
+    /// if there is still no value after `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, HorridError> {
+        self.shared.recv(Instant::now().checked_add(timeout))
+    }
+
+    /// Close the channel for every sender and receiver.
+    /// Values already sent can still be received.
+    pub fn close(&self) {
+        self.shared.close();
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().receivers += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            drop(state);
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_send_recv() {
+        let (tx, rx) = channel(2);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.recv(), Ok(2));
+    }
+
+    #[test]
+    fn test_recv_timeout() {
+        let (_tx, rx) = channel::<u8>(2);
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Err(HorridError::Timeout));
+    }
+
+    #[test]
+    fn test_send_timeout() {
+        let (tx, rx) = channel(1);
+        tx.send(1).unwrap();
+
+        let err = tx.send_timeout(2, Duration::from_millis(10)).unwrap_err();
+        assert_eq!((err.value, err.error), (2, HorridError::Timeout));
+        assert_eq!(rx.recv(), Ok(1));
+    }
+
+    #[test]
+    fn test_dropped_sender_wakes_receiver() {
+        let (tx, rx) = channel::<u8>(2);
+        let tx2 = tx.clone();
+        let handle = thread::spawn(move || rx.recv());
+
+        drop(tx);
+        thread::sleep(Duration::from_millis(10));
+        drop(tx2);
+        assert_eq!(handle.join().unwrap(), Err(HorridError::Disconnected));
+    }
+
+    #[test]
+    fn test_dropped_receiver_wakes_sender() {
+        let (tx, rx) = channel(1);
+        tx.send(1).unwrap();
+        let handle = thread::spawn(move || tx.send(2).map_err(|e| e.error));
+
+        thread::sleep(Duration::from_millis(10));
+        drop(rx);
+        assert_eq!(handle.join().unwrap(), Err(HorridError::Disconnected));
+    }
+
+    #[test]
+    fn test_close() {
+        let (tx, rx) = channel(2);
+        tx.send(1).unwrap();
+        let handle = {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                tx.send(2).unwrap();
+                tx.send(3).map_err(|e| e.error)
+            })
+        };
+
+        thread::sleep(Duration::from_millis(10));
+        rx.close();
+        assert_eq!(handle.join().unwrap(), Err(HorridError::Disconnected));
+        assert_eq!(tx.send(4).map_err(|e| e.error), Err(HorridError::Disconnected));
+
+        // Values sent before closing are still delivered
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.recv(), Ok(2));
+        assert_eq!(rx.recv(), Err(HorridError::Disconnected));
+    }
+
+    #[test]
+    fn test_reject_policy() {
+        let (tx, rx) = channel_with_policy(1, FullPolicy::Reject);
+        tx.send(1).unwrap();
+        assert_eq!(tx.send(2).map_err(|e| e.error), Err(HorridError::Full));
+        assert_eq!(rx.recv(), Ok(1));
+    }
+
+    #[test]
+    fn test_overwrite_policy() {
+        let (tx, rx) = channel_with_policy(2, FullPolicy::OverwriteOldest);
+        for i in 0..5 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.recv(), Ok(3));
+        assert_eq!(rx.recv(), Ok(4));
+    }
+
+    #[test]
+    fn test_many_threads() {
+        let (tx, rx) = channel(3);
+        let senders = (0..4)
+            .map(|t| {
+                let tx = tx.clone();
+                thread::spawn(move || (0..500).for_each(|i| tx.send(t * 500 + i).unwrap()))
+            })
+            .collect::<Vec<_>>();
+        drop(tx);
+
+        let receivers = (0..3)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || std::iter::from_fn(|| rx.recv().ok()).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        drop(rx);
+
+        senders.into_iter().for_each(|h| h.join().unwrap());
+        let mut received = receivers.into_iter().flat_map(|h| h.join().unwrap()).collect::<Vec<_>>();
+        received.sort_unstable();
+        assert_eq!(received, (0..2000).collect::<Vec<_>>());
+    }
+}
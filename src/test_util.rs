@@ -0,0 +1,40 @@
+// Helpers shared by the tests that make names outside the process.
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// A name unique per process and call, so parallel test runs don't
+// collide, which is removed again when dropped even if the test fails.
+pub(crate) struct TempName {
+    name: String,
+    remove: fn(&str),
+}
+
+impl TempName {
+    pub(crate) fn new(prefix: &str, remove: fn(&str)) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{}-{}-{}", prefix, std::process::id(), n);
+        Self { name, remove }
+    }
+}
+
+impl Deref for TempName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl AsRef<Path> for TempName {
+    fn as_ref(&self) -> &Path {
+        self.name.as_ref()
+    }
+}
+
+impl Drop for TempName {
+    fn drop(&mut self) {
+        (self.remove)(&self.name);
+    }
+}